@@ -0,0 +1,123 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Checks the solvers against each other on randomly scrambled boards: whatever find_swaps returns
+// has to turn the scramble into the target when replayed, and has to be as short as min_swaps says
+// is possible, and searching with exact_keys has to find the same thing. Its trace of wrong cells
+// has to have an entry per swap plus one for the start, and never go up. Padding the swaps with
+// one made twice over, then minimizing them, has to give something shorter that still works. Run
+// it with
+//
+//     cargo run --release --example replay [rounds] [seed]
+//
+// Any failure is printed with the seed and round, so it can be reproduced and turned into boards.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, process};
+
+use waffler::board::{Swap, WaffleBoard};
+use waffler::heuristic::DiffHalf;
+use waffler::profile::Profile;
+use waffler::search::{find_swaps, min_swaps, minimize, solve_with_trace, SearchOptions};
+
+// xorshift64, which is plenty for picking cells and keeps the example free of dependencies.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        return (self.state % n as u64) as usize;
+    }
+}
+
+// Makes a handful of random swaps, so that the search stays quick.
+fn scramble(board: &WaffleBoard, rng: &mut Rng) -> WaffleBoard {
+    let cells = board.active_coords();
+
+    let mut ret = board.clone();
+    for _ in 0..rng.below(7) {
+        let a = cells[rng.below(cells.len())];
+        let b = cells[rng.below(cells.len())];
+        if a == b { continue; }
+        ret = ret.swap(Swap::new(a, b));
+    }
+    return ret;
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let rounds: usize = args.first().map_or(1000, |rounds| rounds.parse().expect("Invalid rounds"));
+    let seed: u64 = args.get(1).map_or_else(
+        || SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64,
+        |seed| seed.parse().expect("Invalid seed"));
+
+    let into = WaffleBoard::new(Path::new("src/testdata/into")).expect("Failed to read the target");
+    let mut rng = Rng { state: seed | 1 };
+    let mut failures = 0;
+
+    for round in 0..rounds {
+        let from = scramble(&into, &mut rng);
+        let options = SearchOptions::default();
+        let steps = match find_swaps(&from, &into, &options, DiffHalf, &mut Profile::new(false)) {
+            Some(steps) => steps,
+            None => {
+                println!("seed {} round {}: no path found for\n{}", seed, round, from.display());
+                failures += 1;
+                continue;
+            },
+        };
+
+        if from.apply_all(&steps) != into {
+            println!("seed {} round {}: replaying the swaps misses the target for\n{}",
+                     seed, round, from.display());
+            failures += 1;
+        }
+        let min = min_swaps(&from, &into, options.swap_mode);
+        if min != Some(steps.len()) {
+            println!("seed {} round {}: found {} swaps but min_swaps says {:?} for\n{}",
+                     seed, round, steps.len(), min, from.display());
+            failures += 1;
+        }
+        // Keying the visited boards by hash mustn't change what's found.
+        let exact = SearchOptions { exact_keys: true, ..options };
+        let exact_steps = find_swaps(&from, &into, &exact, DiffHalf, &mut Profile::new(false));
+        if exact_steps.as_ref() != Some(&steps) {
+            println!("seed {} round {}: exact keys found {:?} rather than {:?} for\n{}",
+                     seed, round, exact_steps, steps, from.display());
+            failures += 1;
+        }
+        if let Some((trace_steps, trace)) = solve_with_trace(&from, &into) {
+            if trace.len() != trace_steps.len() + 1 || trace.windows(2).any(|pair| pair[1] > pair[0]) {
+                println!("seed {} round {}: bad trace {:?} for\n{}", seed, round, trace, from.display());
+                failures += 1;
+            }
+        }
+        if let Some(&first) = steps.first() {
+            let padded: Vec<Swap> = [first, first].iter().chain(&steps).copied().collect();
+            let minimized = minimize(&from, &into, &padded);
+            if minimized.len() >= padded.len() || from.apply_all(&minimized) != into {
+                println!("seed {} round {}: minimizing {:?} gave {:?} for\n{}",
+                         seed, round, padded, minimized, from.display());
+                failures += 1;
+            }
+        }
+    }
+
+    println!("{} rounds with seed {}: {} failures", rounds, seed, failures);
+    if failures > 0 { process::exit(1); }
+}
@@ -0,0 +1,315 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use itertools::Itertools;
+use rayon::prelude::*;
+
+use crate::constraints::ConstraintBoard;
+
+type Coord = (usize, usize);
+
+// The cells of a word, and the candidates which could fill them.
+type Slot<'a> = (Vec<Coord>, Vec<&'a str>);
+
+fn try_word(board: ConstraintBoard, word: &str, indices: &[Coord]) -> Option<ConstraintBoard> {
+    assert!(word.chars().count() == indices.len(), "Word and indices are different lengths!");
+    let mut chars = word.chars();
+    let c: char = match chars.next() {
+        Some(c) => c,
+        None => return Some(board),
+    };
+    let (row, col) = indices[0];
+
+    return match board.with(row, col, c) {
+        Some(next) => try_word(next, chars.as_str(), &indices[1..]),
+        None => None,
+    };
+}
+
+// Describes the word which couldn't be filled in, and the letters which were left to fill it with.
+fn dump_budget(board: &ConstraintBoard, indices: &[Coord]) {
+    let pattern: String = indices.iter()
+        .map(|&(row, col)| board.get(row, col).unwrap_or('?'))
+        .collect();
+    let budget = board.unused().iter()
+        .sorted()
+        .map(|(c, count)| format!("{}:{}", c, count))
+        .join(" ");
+    eprintln!("dead end at {} with {} letters left: {}", pattern, board.unused_total(), budget);
+}
+
+// Why a search ended before trying everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    // Whoever was receiving the solutions asked to stop, eg because they only wanted one.
+    Done,
+    // A word had more candidates than max_branch allows.
+    TooManyCandidates { count: usize },
+    // The search went deeper than max_depth.
+    TooDeep { depth: usize },
+    // Filling in a word didn't leave any fewer words to fill, so the search might never finish.
+    NoProgress,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SolverOptions {
+    // The most candidates to consider for any one word. Past that the search stops, unless
+    // truncate is set, in which case only the first max_branch candidates are tried, in the order
+    // of the word list. findanswers puts the most used words first for that, but truncating can
+    // still miss solutions.
+    pub max_branch: Option<usize>,
+    pub truncate: bool,
+    // Print the letters left over whenever no candidate fits the next word.
+    pub dump_budget: bool,
+    // How many words deep the search may go. Each level fills in at least one word, so a board
+    // never needs more levels than it has words.
+    pub max_depth: Option<usize>,
+}
+
+pub struct Solver<'a> {
+    wordlist: &'a [&'a str],
+    options: SolverOptions,
+    // Whether any word has had its candidates cut short by max_branch.
+    truncated: AtomicBool,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(wordlist: &'a [&'a str], options: SolverOptions) -> Self {
+        return Self {
+            wordlist: wordlist,
+            options: options,
+            truncated: AtomicBool::new(false),
+        };
+    }
+
+    pub fn truncated(&self) -> bool {
+        return self.truncated.load(Ordering::Relaxed);
+    }
+
+    // The cells of the next word to fill in, along with every word that could go there. Returns
+    // Continue(None) if the board has no words left to fill.
+    fn next_word(&self, board: &ConstraintBoard) -> ControlFlow<Stop, Option<Slot<'a>>> {
+        let (constraint, indices) = match board.get_all_words().into_iter().next() {
+            Some(word) => word,
+            None => return ControlFlow::Continue(None),
+        };
+        let mut possible_words: Vec<&str> = self.wordlist.iter()
+            .copied()
+            .filter(|word| word.chars().count() == indices.len() && constraint.matches(word))
+            .collect();
+
+        if let Some(max_branch) = self.options.max_branch {
+            if possible_words.len() > max_branch {
+                if !self.options.truncate {
+                    return ControlFlow::Break(Stop::TooManyCandidates { count: possible_words.len() });
+                }
+                possible_words.truncate(max_branch);
+                self.truncated.store(true, Ordering::Relaxed);
+            }
+        }
+        return ControlFlow::Continue(Some((indices, possible_words)));
+    }
+
+    // Fills in the word, checking that doing so makes progress. Continue(None) if it doesn't fit.
+    fn place(&self, board: &ConstraintBoard, word: &str,
+             indices: &[Coord]) -> ControlFlow<Stop, Option<ConstraintBoard>> {
+        let next = match try_word(board.clone(), word, indices) {
+            None => return ControlFlow::Continue(None),
+            Some(next) => next,
+        };
+        if next.unfilled_words() >= board.unfilled_words() {
+            return ControlFlow::Break(Stop::NoProgress);
+        }
+        return ControlFlow::Continue(Some(next));
+    }
+
+    // Passes each solution to found, stopping early if it returns ControlFlow::Break.
+    pub fn find_solutions(&self, board: ConstraintBoard,
+                          found: &mut dyn FnMut(&ConstraintBoard) -> ControlFlow<Stop>) -> ControlFlow<Stop> {
+        return self.find_solutions_from(board, 0, found);
+    }
+
+    fn find_solutions_from(&self, board: ConstraintBoard, depth: usize,
+                           found: &mut dyn FnMut(&ConstraintBoard) -> ControlFlow<Stop>) -> ControlFlow<Stop> {
+        if self.options.max_depth.is_some_and(|max_depth| depth > max_depth) {
+            return ControlFlow::Break(Stop::TooDeep { depth: depth });
+        }
+        let (indices, possible_words) = match self.next_word(&board)? {
+            Some(next) => next,
+            None => return found(&board),
+        };
+
+        let mut dead_end = true;
+        for possible_word in possible_words {
+            let next = match self.place(&board, possible_word, &indices)? {
+                None => continue,
+                Some(next) => next,
+            };
+            dead_end = false;
+            self.find_solutions_from(next, depth + 1, found)?;
+        }
+        if dead_end && self.options.dump_budget { dump_budget(&board, &indices); }
+        return ControlFlow::Continue(());
+    }
+
+    // Explores each candidate for the first word on its own thread. Every branch gets its own copy
+    // of the board, so they're entirely independent of each other. Each solution is tagged with
+    // the candidate whose branch found it, so they can be put back in the order find_solutions
+    // would have found them in. With first set, only one solution is returned, and the branches
+    // after the earliest one to find a solution are abandoned.
+    pub fn find_solutions_parallel(&self, board: ConstraintBoard, jobs: usize,
+                                   first: bool) -> Result<Vec<ConstraintBoard>, Stop> {
+        let (indices, possible_words) = match self.next_word(&board) {
+            ControlFlow::Break(stop) => return Err(stop),
+            ControlFlow::Continue(None) => return Ok(vec![board]),
+            ControlFlow::Continue(Some(next)) => next,
+        };
+
+        let found = Mutex::new(Vec::new());
+        // The earliest branch to have found a solution, when only the first one is wanted.
+        let earliest = AtomicUsize::new(usize::MAX);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .expect("Failed to start worker threads");
+        let branches = possible_words.par_iter().enumerate();
+        let result = pool.install(|| branches.try_for_each(|(branch, possible_word)| {
+            if first && earliest.load(Ordering::Relaxed) < branch {
+                return ControlFlow::Continue(());
+            }
+            let next = match self.place(&board, possible_word, &indices)? {
+                None => return ControlFlow::Continue(()),
+                Some(next) => next,
+            };
+            let result = self.find_solutions_from(next, 1, &mut |solution| {
+                found.lock().unwrap().push((branch, solution.clone()));
+                if !first { return ControlFlow::Continue(()); }
+                earliest.fetch_min(branch, Ordering::Relaxed);
+                return ControlFlow::Break(Stop::Done);
+            });
+            // Finishing one branch early shouldn't stop the others.
+            return match result {
+                ControlFlow::Break(Stop::Done) => ControlFlow::Continue(()),
+                result => result,
+            };
+        }));
+        if let ControlFlow::Break(stop) = result { return Err(stop); }
+
+        // A stable sort, as each branch found its own solutions in order.
+        let mut found = found.into_inner().unwrap();
+        found.sort_by_key(|&(branch, _)| branch);
+        if first { found.truncate(1); }
+        return Ok(found.into_iter().map(|(_, solution)| solution).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::constraint_board;
+
+    const WORDS: [&str; 6] = ["loyal", "locus", "chair", "lilac", "yucca", "loser"];
+
+    #[test]
+    fn breaking_stops_the_search_at_the_first_solution() {
+        // Only the corners on the diagonal are known, so the answer and its transpose both fit.
+        let board = constraint_board("
+            Loyal
+            i u o
+            locus
+            a c e
+            chaiR
+        ");
+        let solver = Solver::new(&WORDS, SolverOptions::default());
+        let mut all = Vec::new();
+        let result = solver.find_solutions(board.clone(), &mut |solution| {
+            all.push(solution.to_string());
+            return ControlFlow::Continue(());
+        });
+        assert_eq!(result, ControlFlow::Continue(()));
+        assert_eq!(all.len(), 2);
+
+        let mut first = Vec::new();
+        let result = solver.find_solutions(board, &mut |solution| {
+            first.push(solution.to_string());
+            return ControlFlow::Break(Stop::Done);
+        });
+        assert_eq!(result, ControlFlow::Break(Stop::Done));
+        assert_eq!(first, all[..1]);
+    }
+
+    #[test]
+    fn parallel_solutions_come_in_the_serial_order() {
+        // Every word of two a's and three b's, which fill this board in dozens of ways.
+        let words: Vec<String> = (0..32u32)
+            .filter(|bits| bits.count_ones() == 2)
+            .map(|bits| (0..5).map(|i| if bits & (1 << i) != 0 { 'a' } else { 'b' }).collect())
+            .collect();
+        let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+        let board = constraint_board("
+            ababa
+            b a b
+            ababa
+            b a b
+            ababa
+        ");
+        let solver = Solver::new(&wordlist, SolverOptions::default());
+        let mut serial = Vec::new();
+        let _ = solver.find_solutions(board.clone(), &mut |solution| {
+            serial.push(solution.to_string());
+            return ControlFlow::Continue(());
+        });
+        assert_eq!(serial.len(), 36);
+
+        for jobs in [2, 3, 4] {
+            let parallel: Vec<String> = solver.find_solutions_parallel(board.clone(), jobs, false)
+                .unwrap()
+                .iter()
+                .map(ConstraintBoard::to_string)
+                .collect();
+            assert_eq!(parallel, serial);
+            let first = solver.find_solutions_parallel(board.clone(), jobs, true).unwrap();
+            assert_eq!(first.iter().map(ConstraintBoard::to_string).collect::<Vec<_>>(), serial[..1]);
+        }
+    }
+
+    #[test]
+    fn a_standard_board_is_solved_within_one_level_per_word() {
+        let board = constraint_board("
+            LOYAL
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        // Every word but the known one is left to fill, one per level, so it takes five levels.
+        let depth = |max_depth| {
+            let solver = Solver::new(&WORDS, SolverOptions { max_depth: Some(max_depth),
+                                                             ..SolverOptions::default() });
+            let mut found = 0;
+            let result = solver.find_solutions(board.clone(), &mut |_| {
+                found += 1;
+                return ControlFlow::Continue(());
+            });
+            return (result, found);
+        };
+        assert_eq!(depth(5), (ControlFlow::Continue(()), 1));
+        assert_eq!(depth(6), (ControlFlow::Continue(()), 1));
+        assert_eq!(depth(4), (ControlFlow::Break(Stop::TooDeep { depth: 5 }), 0));
+        assert_eq!(depth(1), (ControlFlow::Break(Stop::TooDeep { depth: 2 }), 0));
+    }
+}
@@ -0,0 +1,209 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+use std::ops::ControlFlow;
+
+use crate::answers::{Solver, SolverOptions};
+use crate::board::{BoardError, Coord, Swap, SwapMode, WaffleBoard};
+use crate::constraints::ConstraintBoard;
+use crate::dictionary::Dictionary;
+use crate::heuristic::{CycleBound, Heuristic};
+use crate::search::find_min_swaps;
+
+// The colours are given as a grid the same shape as the board, with a 'g' for each green tile.
+// Every other tile is treated the same, as only the greens are used to narrow down the answer.
+pub const GREEN: char = 'g';
+
+// Builds the findanswers input for the scramble: greens are known, so are written in uppercase,
+// and everything else is a lowercase letter whose position is unknown. Fails if the scramble holds
+// something which can't be a tile.
+pub fn constraint_board(letters: &WaffleBoard,
+                        colors: &WaffleBoard) -> Result<ConstraintBoard, BoardError> {
+    let (rows, cols) = letters.size();
+    let text = (0..rows)
+        .map(|row| (0..cols)
+            .map(|col| Coord{ row: row, col: col })
+            .map(|coord| match (letters.layout().is_gap(coord), colors.get(coord) == GREEN) {
+                (true, _) => ' ',
+                (false, true) => letters.get(coord).to_ascii_uppercase(),
+                (false, false) => letters.get(coord).to_ascii_lowercase(),
+            })
+            .collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n");
+    return text.parse();
+}
+
+// How to choose between several solved boards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Prefer {
+    // The board with the most common words, by board_likelihood, then the fewest swaps.
+    #[default]
+    Likelihood,
+    // The board needing the fewest swaps, then the one with the most common words.
+    Swaps,
+}
+
+impl Prefer {
+    pub fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "likelihood" => Some(Self::Likelihood),
+            "swaps" => Some(Self::Swaps),
+            _ => None,
+        };
+    }
+}
+
+// The total frequency of the board's words. Words the dictionary has no frequency for count for
+// nothing, so with a plain word list every board scores 0.
+pub fn board_likelihood(board: &WaffleBoard, dict: &Dictionary) -> f64 {
+    return board.word_cells().iter()
+        .map(|cells| cells.iter().map(|&coord| board.get(coord).to_ascii_lowercase()).collect())
+        .map(|word: String| dict.frequency(&word))
+        .sum();
+}
+
+// Deduces the solved board from the scramble and its colours, then finds the swaps to get there.
+// If the colours allow more than one solved board, one is chosen as prefer says, with any ties left
+// going to whichever comes first alphabetically. None if no board fits, or an error if the scramble
+// holds something which can't be a tile.
+pub fn auto_solve(letters: &WaffleBoard, colors: &WaffleBoard, dict: &Dictionary,
+                  prefer: Prefer) -> Result<Option<(WaffleBoard, Vec<Swap>)>, BoardError> {
+    let lowercase = letters.normalized();
+    let constraints = constraint_board(&lowercase, colors)?;
+    return Ok(closest_solution(&lowercase, constraints, dict, prefer));
+}
+
+// The same as auto_solve, but without the colours: every board that can be made out of the
+// scramble's letters is considered, so this can be much slower.
+pub fn best_reachable(scramble: &WaffleBoard, dict: &Dictionary,
+                      prefer: Prefer) -> Result<Option<(WaffleBoard, Vec<Swap>)>, BoardError> {
+    let lowercase = scramble.normalized();
+    let constraints = lowercase.display().parse()?;
+    return Ok(closest_solution(&lowercase, constraints, dict, prefer));
+}
+
+// Of the boards which fill in the constraints, the one prefer picks out, with ties going to
+// whichever comes first alphabetically.
+fn closest_solution(scramble: &WaffleBoard, constraints: ConstraintBoard, dict: &Dictionary,
+                    prefer: Prefer) -> Option<(WaffleBoard, Vec<Swap>)> {
+    let mut candidates = Vec::new();
+    let wordlist = dict.words();
+    let solver = Solver::new(&wordlist, SolverOptions::default());
+    let _ = solver.find_solutions(constraints, &mut |solution| {
+        candidates.push(solution.to_string().parse::<WaffleBoard>().unwrap());
+        return ControlFlow::Continue(());
+    });
+
+    // Different routes through the word list can arrive at the same board. Words which were
+    // entirely green to begin with were never looked up, so may not be words at all.
+    candidates.sort_by(|a, b| a.cells().cmp(b.cells()));
+    candidates.dedup();
+    candidates.retain(|candidate| candidate.is_valid_waffle(dict));
+
+    // Compares two boards as prefer says, given how many swaps each needs.
+    let order = |(a, a_likelihood, a_swaps): (&WaffleBoard, f64, usize),
+                 (b, b_likelihood, b_swaps): (&WaffleBoard, f64, usize)| {
+        // Higher likelihoods come first.
+        let likelihood = b_likelihood.total_cmp(&a_likelihood);
+        let swaps = a_swaps.cmp(&b_swaps);
+        let preferred = match prefer {
+            Prefer::Likelihood => likelihood.then(swaps),
+            Prefer::Swaps => swaps.then(likelihood),
+        };
+        return preferred.then_with(|| a.cells().cmp(b.cells()));
+    };
+
+    // Working out exactly how many swaps a board needs can be slow, so the boards are taken in
+    // order of CycleBound's lower bound on that, and the rest are skipped once even the bound
+    // can't beat the best board so far.
+    let mut bounded: Vec<(WaffleBoard, f64, usize)> = candidates.into_iter()
+        .map(|into| {
+            let likelihood = board_likelihood(&into, dict);
+            let bound = CycleBound.estimate(scramble, &into);
+            (into, likelihood, bound)
+        })
+        .collect();
+    bounded.sort_by(|a, b| order((&a.0, a.1, a.2), (&b.0, b.1, b.2)));
+
+    let mut best: Option<(WaffleBoard, f64, Vec<Swap>)> = None;
+    for (into, likelihood, bound) in bounded {
+        if let Some((best_into, best_likelihood, best_steps)) = &best {
+            let best_key = (best_into, *best_likelihood, best_steps.len());
+            if order((&into, likelihood, bound), best_key).is_ge() { break; }
+        }
+        let steps = match find_min_swaps(scramble, &into, SwapMode::Any) {
+            Some(steps) => steps,
+            None => continue,
+        };
+        let better = best.as_ref().is_none_or(|(best_into, best_likelihood, best_steps)| {
+            let best_key = (best_into, *best_likelihood, best_steps.len());
+            return order((&into, likelihood, steps.len()), best_key).is_lt();
+        });
+        if better { best = Some((into, likelihood, steps)); }
+    }
+    return best.map(|(into, _, steps)| (into, steps));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{board, swaps};
+
+    fn dictionary(words: &[&str]) -> Dictionary {
+        return Dictionary::new(words.iter().map(|word| word.to_string()));
+    }
+
+    #[test]
+    fn best_reachable_prefers_fewer_swaps_then_alphabetical_order() {
+        // Two boards can be made from these letters: layol and lucus across, or luyal and locus.
+        let dict = dictionary(&["layol", "lucus", "luyal", "locus", "chair", "lilac", "yucca",
+                                "loser"]);
+        let layol = board("
+            layol
+            i u o
+            lucus
+            a c e
+            chair
+        ");
+        let luyal = board("
+            luyal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+
+        // Each is a single swap away, so the first alphabetically wins.
+        let between = board("
+            loyal
+            i u o
+            lucus
+            a c e
+            chair
+        ");
+        let found = best_reachable(&between, &dict, Prefer::default()).unwrap();
+        assert_eq!(found, Some((layol, swaps("0,1-0,3"))));
+
+        // This is one swap from luyal, but three from layol.
+        let nearer = board("
+            luyal
+            i u o
+            locus
+            a c e
+            cahir
+        ");
+        let found = best_reachable(&nearer, &dict, Prefer::default()).unwrap();
+        assert_eq!(found, Some((luyal, swaps("4,1-4,2"))));
+    }
+}
@@ -0,0 +1,1236 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::{cmp, fmt, fs, io};
+
+use itertools::Itertools;
+
+use crate::dictionary::Dictionary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coord {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "({},{})", self.row, self.col);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoordStyle {
+    // (row,col), counting from 0.
+    #[default]
+    Zero,
+    // (row,col), counting from 1.
+    One,
+    // Spreadsheet style: a column letter followed by a 1-based row, eg B3.
+    A1,
+}
+
+impl CoordStyle {
+    pub fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "zero" => Some(Self::Zero),
+            "one" => Some(Self::One),
+            "a1" => Some(Self::A1),
+            _ => None,
+        };
+    }
+}
+
+impl Coord {
+    pub fn format(&self, style: CoordStyle) -> String {
+        return match style {
+            CoordStyle::Zero => self.to_string(),
+            CoordStyle::One => format!("({},{})", self.row + 1, self.col + 1),
+            CoordStyle::A1 => {
+                // Bijective base 26, so that column 26 is AA rather than BA.
+                let mut letters = Vec::new();
+                let mut col = self.col + 1;
+                while col > 0 {
+                    col -= 1;
+                    letters.push((b'A' + (col % 26) as u8) as char);
+                    col /= 26;
+                }
+                letters.into_iter().rev().chain((self.row + 1).to_string().chars()).collect()
+            },
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Swap {
+    pub a: Coord,
+    pub b: Coord,
+}
+
+impl Swap {
+    pub fn new(a: Coord, b: Coord) -> Self {
+        let first = cmp::min(a, b);
+        let second = cmp::max(a, b);
+        return Self { a: first, b: second };
+    }
+
+    // Whether the two swaps share a cell, meaning the order they are made in matters.
+    pub fn overlaps(&self, other: Swap) -> bool {
+        return [self.a, self.b].iter().any(|&coord| coord == other.a || coord == other.b);
+    }
+
+    // Swapping a gap would move a letter out of the words, and leave a hole in one of them.
+    pub fn touches_gap(&self, layout: WaffleLayout) -> bool {
+        return layout.is_gap(self.a) || layout.is_gap(self.b);
+    }
+}
+
+// Written as r1,c1-r2,c2 with 0-based coordinates, which is easy for other tools to read back in.
+impl fmt::Display for Swap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{},{}-{},{}", self.a.row, self.a.col, self.b.row, self.b.col);
+    }
+}
+
+// Reads back what Display writes. A list of swaps, such as one per line from findswaps --format
+// swaps, can be read with eg text.split_whitespace().map(str::parse).collect().
+impl FromStr for Swap {
+    type Err = BoardError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || BoardError::InvalidSwap { text: text.to_owned() };
+        let coord = |text: &str| -> Result<Coord, BoardError> {
+            let (row, col) = text.split_once(',').ok_or_else(invalid)?;
+            let row = row.trim().parse().map_err(|_| invalid())?;
+            let col = col.trim().parse().map_err(|_| invalid())?;
+            return Ok(Coord{ row: row, col: col });
+        };
+        let (a, b) = text.trim().split_once('-').ok_or_else(invalid)?;
+        return Ok(Swap::new(coord(a)?, coord(b)?));
+    }
+}
+
+// Which pairs of cells may be swapped. Some puzzle variants only allow swaps along a line, in
+// which case boards that need letters to cross between rows or columns may become unsolvable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SwapMode {
+    #[default]
+    Any,
+    Row,
+    Col,
+    // Either the same row or the same column.
+    Line,
+}
+
+impl SwapMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "any" => Some(Self::Any),
+            "row" => Some(Self::Row),
+            "col" => Some(Self::Col),
+            "line" => Some(Self::Line),
+            _ => None,
+        };
+    }
+
+    pub fn allows(&self, swap: Swap) -> bool {
+        let same_row = swap.a.row == swap.b.row;
+        let same_col = swap.a.col == swap.b.col;
+        return match self {
+            Self::Any => true,
+            Self::Row => same_row,
+            Self::Col => same_col,
+            Self::Line => same_row || same_col,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardError {
+    Empty,
+    UnevenLines,
+    // A cell breaks the waffle lattice: either a gap where a letter belongs, or a letter where a
+    // gap belongs.
+    MalformedWaffle { coord: Coord, found: char },
+    // A swap tried to move the gap at coord.
+    GapSwap { coord: Coord },
+    // A word assignment which isn't of the form row<n>=<word> or col<n>=<word>, names a row or
+    // column with no word in it, or is the wrong length.
+    InvalidAssignment { text: String },
+    // Two words which cross disagree about the letter where they do.
+    ConflictingLetters { coord: Coord, first: char, second: char },
+    // No word covers the letter at coord.
+    MissingLetter { coord: Coord },
+    // Two boards which were meant to be compared aren't the same shape.
+    SizeMismatch { first: (usize, usize), second: (usize, usize) },
+    // Two boards have a gap in different places; one of them has a gap at coord and the other
+    // doesn't.
+    GapMismatch { coord: Coord },
+    // Text which isn't a swap written as r1,c1-r2,c2.
+    InvalidSwap { text: String },
+    // A cell which isn't on the board at all.
+    OffBoard { coord: Coord },
+    // The operation only makes sense for square boards.
+    NotSquare { rows: usize, cols: usize },
+    // A line locking in a word, like =TRAIN@row0, which is malformed, or whose word doesn't fit.
+    InvalidLock { text: String },
+    // A letter which can't be used as a tile, as its lowercase is more than one character.
+    UnsupportedChar { ch: char },
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Self::Empty => write!(f, "Expected at least one line"),
+            Self::UnevenLines => write!(f, "Expected all lines to be the same length"),
+            Self::MalformedWaffle { coord, found } if is_gap_char(*found) =>
+                write!(f, "Malformed waffle: unexpected gap at {}", coord),
+            Self::MalformedWaffle { coord, found } =>
+                write!(f, "Malformed waffle: expected a gap at {} but found '{}'", coord, found),
+            Self::GapSwap { coord } => write!(f, "Cannot swap the gap at {}", coord),
+            Self::InvalidAssignment { text } => write!(f, "Invalid word assignment: {}", text),
+            Self::ConflictingLetters { coord, first, second } =>
+                write!(f, "Words disagree at {}: '{}' vs '{}'", coord, first, second),
+            Self::MissingLetter { coord } => write!(f, "No word covers {}", coord),
+            Self::SizeMismatch { first, second } =>
+                write!(f, "The boards are different sizes: {}x{} vs {}x{}",
+                       first.0, first.1, second.0, second.1),
+            Self::GapMismatch { coord } =>
+                write!(f, "Only one of the boards has a gap at {}", coord),
+            Self::InvalidSwap { text } =>
+                write!(f, "Expected a swap like 0,1-2,3 but got {}", text),
+            Self::OffBoard { coord } => write!(f, "{} is off the board", coord),
+            Self::NotSquare { rows, cols } =>
+                write!(f, "Expected a square board, but it is {}x{}", rows, cols),
+            Self::InvalidLock { text } => write!(f, "Cannot lock in {}", text),
+            Self::UnsupportedChar { ch } => write!(f, "Unsupported character '{}'", ch),
+        };
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+impl From<BoardError> for io::Error {
+    fn from(err: BoardError) -> Self {
+        return io::Error::new(io::ErrorKind::InvalidData, err);
+    }
+}
+
+// Gaps sit exactly where an odd row crosses an odd column. Every other cell belongs to at least one
+// word and so must hold a letter. Gaps may be written as a space or a '.', but are always stored
+// as a space.
+pub const GAP: char = ' ';
+const ALT_GAP: char = '.';
+
+pub fn is_gap_char(c: char) -> bool {
+    return c == GAP || c == ALT_GAP;
+}
+
+fn normalize_gap(c: char) -> char {
+    return if c == ALT_GAP { GAP } else { c };
+}
+
+// The lines of a board file. Some tools start their files with a byte order mark or a few blank
+// lines, which would otherwise look like a row of the board, so those are dropped. Only the very
+// start of the text is affected.
+pub fn board_lines(text: &str) -> impl Iterator<Item = &str> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    return text.lines().skip_while(|line| line.trim().len() == 0);
+}
+
+// The shape of a waffle: which cells hold letters, which are gaps, and which cells make up each
+// word. Every row and column at an even index is a word, and the gaps sit where the odd ones cross.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WaffleLayout {
+    rows: usize,
+    cols: usize,
+}
+
+impl WaffleLayout {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        return Self { rows: rows, cols: cols };
+    }
+
+    // The daily waffle: 21 tiles around 4 gaps, making 3 words across and 3 down.
+    pub fn standard_5x5() -> Self {
+        return Self::new(5, 5);
+    }
+
+    // The weekly deluxe waffle: 40 tiles around 9 gaps, making 4 words across and 4 down.
+    pub fn deluxe_7x7() -> Self {
+        return Self::new(7, 7);
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        return (self.rows, self.cols);
+    }
+
+    pub fn contains(&self, coord: Coord) -> bool {
+        return coord.row < self.rows && coord.col < self.cols;
+    }
+
+    pub fn is_gap(&self, coord: Coord) -> bool {
+        return self.contains(coord) && coord.row % 2 == 1 && coord.col % 2 == 1;
+    }
+
+    // Every cell, gap or not, row by row.
+    pub fn coords(&self) -> impl Iterator<Item = Coord> {
+        let cols = self.cols;
+        return (0..self.rows)
+            .flat_map(move |row| (0..cols).map(move |col| Coord{ row: row, col: col }));
+    }
+
+    // Every cell which holds a letter, row by row.
+    pub fn active_cells(&self) -> Vec<Coord> {
+        return self.coords().filter(|&coord| !self.is_gap(coord)).collect();
+    }
+
+    // The cells of each word in reading order: the rows from top to bottom, then the columns from
+    // left to right.
+    pub fn word_cells(&self) -> Vec<Vec<Coord>> {
+        let mut ret = Vec::new();
+        for row in (0..self.rows).step_by(2) {
+            ret.push((0..self.cols).map(|col| Coord{ row: row, col: col }).collect());
+        }
+        for col in (0..self.cols).step_by(2) {
+            ret.push((0..self.rows).map(|row| Coord{ row: row, col: col }).collect());
+        }
+        return ret;
+    }
+
+    // Checks that cells fit the layout, with letters in every active cell and gaps between them.
+    pub fn check(&self, cells: &[Vec<char>]) -> Result<(), BoardError> {
+        if cells.len() == 0 { return Err(BoardError::Empty); }
+        let size = (cells.len(), cells[0].len());
+        if !cells.iter().all(|line| line.len() == size.1) { return Err(BoardError::UnevenLines); }
+        if size != self.size() {
+            return Err(BoardError::SizeMismatch { first: size, second: self.size() });
+        }
+        for coord in self.coords() {
+            let found = cells[coord.row][coord.col];
+            if self.is_gap(coord) == (found == GAP) { continue; }
+            return Err(BoardError::MalformedWaffle { coord: coord, found: found });
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CellStatus {
+    Correct,
+    Wrong,
+    Gap,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "SavedBoard"))]
+pub struct WaffleBoard {
+    // Private, so that the cells can only change in ways which keep active right.
+    cells: Vec<Vec<char>>,
+    // Every cell which isn't a gap, in order. This only depends on the size of the board, so
+    // boards made from one another share it rather than working it out again.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    active: Arc<[Coord]>,
+}
+
+// Only the cells are saved, as everything else can be worked out from them.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SavedBoard {
+    cells: Vec<Vec<char>>,
+}
+
+#[cfg(feature = "serde")]
+impl From<SavedBoard> for WaffleBoard {
+    fn from(saved: SavedBoard) -> Self {
+        return Self::with_cells(saved.cells);
+    }
+}
+
+fn active_coords(cells: &[Vec<char>]) -> Arc<[Coord]> {
+    let cols = cells.first().map_or(0, Vec::len);
+    return WaffleLayout::new(cells.len(), cols).active_cells().into();
+}
+
+impl PartialEq for WaffleBoard {
+    fn eq(&self, other: &Self) -> bool {
+        return self.cells == other.cells;
+    }
+}
+
+impl Eq for WaffleBoard {}
+
+impl Hash for WaffleBoard {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cells.hash(state);
+    }
+}
+
+impl FromStr for WaffleBoard {
+    type Err = BoardError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        return Self::from_cells(board_lines(text).map(|line| line.chars().collect()).collect());
+    }
+}
+
+impl Clone for WaffleBoard {
+    fn clone(&self) -> Self {
+        return Self {
+            cells: self.cells.to_vec(),
+            active: self.active.clone(),
+        };
+    }
+}
+
+impl WaffleBoard {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        return Ok(fs::read_to_string(path)?.parse()?);
+    }
+
+    // A board holding cells, as they are.
+    pub(crate) fn with_cells(cells: Vec<Vec<char>>) -> Self {
+        let active = active_coords(&cells);
+        return Self { cells: cells, active: active };
+    }
+
+    // Another board of the same size as this one, which can share its active cells.
+    pub(crate) fn sized_like(&self, cells: Vec<Vec<char>>) -> Self {
+        return Self { cells: cells, active: self.active.clone() };
+    }
+
+    // Checks the cells make a board, whichever way they were read in.
+    fn from_cells(cells: Vec<Vec<char>>) -> Result<Self, BoardError> {
+        if cells.len() == 0 { return Err(BoardError::Empty); }
+        let len = cells[0].len();
+        if !cells.iter().all(|line| line.len() == len) { return Err(BoardError::UnevenLines); }
+
+        let ret = Self::with_cells(cells.into_iter()
+            .map(|line| line.into_iter().map(normalize_gap).collect())
+            .collect());
+        ret.validate()?;
+        return Ok(ret);
+    }
+
+    // Builds a board from its words, written as eg "row0=loyal, row2=locus, col0=llama", numbering
+    // rows and columns the same way coordinates do. Assignments may be separated by commas or
+    // whitespace. Every letter has to be covered by some word, and crossing words have to agree.
+    pub fn from_words(text: &str) -> Result<Self, BoardError> {
+        let mut assignments = Vec::new();
+        let items = text.split(|c: char| c == ',' || c.is_whitespace());
+        for item in items.filter(|item| item.len() > 0) {
+            let invalid = || BoardError::InvalidAssignment { text: item.to_owned() };
+            let (slot, word) = item.split_once('=').ok_or_else(invalid)?;
+            let (is_row, index) = match (slot.strip_prefix("row"), slot.strip_prefix("col")) {
+                (Some(index), _) => (true, index),
+                (_, Some(index)) => (false, index),
+                _ => return Err(invalid()),
+            };
+            let index: usize = index.parse().map_err(|_| invalid())?;
+            assignments.push((is_row, index, word.chars().collect::<Vec<char>>(), item));
+        }
+        if assignments.len() == 0 { return Err(BoardError::Empty); }
+
+        let size = assignments[0].2.len();
+        let mut cells: Vec<Vec<Option<char>>> = vec![vec![None; size]; size];
+        for (is_row, index, word, item) in assignments {
+            if word.len() != size || index >= size || index % 2 == 1 {
+                return Err(BoardError::InvalidAssignment { text: item.to_owned() });
+            }
+            for (i, c) in word.into_iter().enumerate() {
+                let coord = match is_row {
+                    true => Coord{ row: index, col: i },
+                    false => Coord{ row: i, col: index },
+                };
+                match cells[coord.row][coord.col] {
+                    Some(first) if first != c => {
+                        return Err(BoardError::ConflictingLetters {
+                            coord: coord, first: first, second: c,
+                        });
+                    },
+                    _ => cells[coord.row][coord.col] = Some(c),
+                }
+            }
+        }
+
+        let layout = WaffleLayout::new(size, size);
+        let mut rows = Vec::new();
+        for (row, line) in cells.into_iter().enumerate() {
+            let mut chars = Vec::new();
+            for (col, cell) in line.into_iter().enumerate() {
+                let coord = Coord{ row: row, col: col };
+                chars.push(match cell {
+                    _ if layout.is_gap(coord) => GAP,
+                    Some(c) => c,
+                    None => return Err(BoardError::MissingLetter { coord: coord }),
+                });
+            }
+            rows.push(chars);
+        }
+        return Self::from_cells(rows);
+    }
+
+    // The board as {"cells": [["l", "o", ...], ...]}, one list per row.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        return serde_json::to_string(self).expect("A board should always serialize");
+    }
+
+    // Reads a board written by to_json, checking it just as reading one from text would.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let board: Self = serde_json::from_str(json)?;
+        return Ok(Self::from_cells(board.cells)?);
+    }
+
+    // Checks that the board has the waffle layout: letters on every even row and column, and gaps
+    // at the odd-odd intersections.
+    pub fn validate(&self) -> Result<(), BoardError> {
+        return self.layout().check(&self.cells);
+    }
+
+    // Panics if the swap touches a gap; use try_swap for swaps which might.
+    pub fn swap(&self, swap: Swap) -> Self {
+        let mut ret = self.clone();
+        ret.swap_mut(swap);
+        return ret;
+    }
+
+    // The same as swap, but changing this board rather than making a new one.
+    pub fn swap_mut(&mut self, swap: Swap) {
+        assert!(!swap.touches_gap(self.layout()), "Cannot swap a gap: {}", swap);
+        let Swap { a, b } = swap;
+        let c = &mut self.cells;
+        (c[a.row][a.col], c[b.row][b.col]) = (c[b.row][b.col], c[a.row][a.col]);
+    }
+
+    // A copy of the board with every letter in lowercase and every gap written the same way, so
+    // that boards which only differ in how they were written compare equal.
+    pub fn normalized(&self) -> Self {
+        // A letter whose lowercase is more than one character is left alone, rather than cut short
+        // into some other letter.
+        let normalize = |c: char| {
+            let mut lowercase = normalize_gap(c).to_lowercase();
+            return match (lowercase.next(), lowercase.next()) {
+                (Some(lower), None) => lower,
+                _ => c,
+            };
+        };
+        return self.sized_like(self.cells.iter()
+            .map(|row| row.iter().copied().map(normalize).collect())
+            .collect());
+    }
+
+    // Returns a copy of the board with a single cell changed. None if coord is off the board or is
+    // a gap, or if c is a gap, as any of those would break the board.
+    pub fn with_cell(&self, coord: Coord, c: char) -> Option<Self> {
+        let layout = self.layout();
+        if !layout.contains(coord) || layout.is_gap(coord) || is_gap_char(c) { return None; }
+        let mut cells = self.cells.clone();
+        cells[coord.row][coord.col] = c;
+        return Some(self.sized_like(cells));
+    }
+
+    // Changes the letter at coord in place. Like with_cell, this refuses to write to a gap or to
+    // write a gap, as either would break the board.
+    pub fn set(&mut self, coord: Coord, c: char) -> Result<(), BoardError> {
+        let layout = self.layout();
+        if !layout.contains(coord) { return Err(BoardError::OffBoard { coord: coord }); }
+        if layout.is_gap(coord) || is_gap_char(c) {
+            return Err(BoardError::MalformedWaffle { coord: coord, found: c });
+        }
+        self.cells[coord.row][coord.col] = c;
+        return Ok(());
+    }
+
+    // Writes word across row, which has to be one of the rows words go in, and as long as it. The
+    // word can't have any gaps in it.
+    pub fn set_row(&mut self, row: usize, word: &str) -> Result<(), BoardError> {
+        let (rows, cols) = self.size();
+        let fits = word.chars().count() == cols && !word.contains([GAP, ALT_GAP]);
+        if row >= rows || row % 2 == 1 || !fits {
+            return Err(BoardError::InvalidAssignment { text: format!("row{}={}", row, word) });
+        }
+        for (col, c) in word.chars().enumerate() { self.cells[row][col] = c; }
+        return Ok(());
+    }
+
+    // Writes word down col, which has to be one of the columns words go in, and as long as it.
+    pub fn set_col(&mut self, col: usize, word: &str) -> Result<(), BoardError> {
+        let (rows, cols) = self.size();
+        let fits = word.chars().count() == rows && !word.contains([GAP, ALT_GAP]);
+        if col >= cols || col % 2 == 1 || !fits {
+            return Err(BoardError::InvalidAssignment { text: format!("col{}={}", col, word) });
+        }
+        for (row, c) in word.chars().enumerate() { self.cells[row][col] = c; }
+        return Ok(());
+    }
+
+    // Describes the swap in terms of this board, eg "swap 'u' at (2,1) with 'o' at (2,3)".
+    pub fn describe_swap(&self, swap: Swap, style: CoordStyle) -> String {
+        return format!("swap '{}' at {} with '{}' at {}",
+                       self.get(swap.a), swap.a.format(style),
+                       self.get(swap.b), swap.b.format(style));
+    }
+
+    pub fn try_swap(&self, swap: Swap) -> Result<Self, BoardError> {
+        let layout = self.layout();
+        if let Some(&coord) = [swap.a, swap.b].iter().find(|&&coord| layout.is_gap(coord)) {
+            return Err(BoardError::GapSwap { coord: coord });
+        }
+        return Ok(self.swap(swap));
+    }
+
+    pub fn apply_all(&self, swaps: &[Swap]) -> Self {
+        return swaps.iter().fold(self.clone(), |board, &swap| board.swap(swap));
+    }
+
+    pub fn try_apply_all(&self, swaps: &[Swap]) -> Result<Self, BoardError> {
+        return swaps.iter().try_fold(self.clone(), |board, &swap| board.try_swap(swap));
+    }
+
+    // A quick hash of the cells. Equal boards always have the same checksum, so differing checksums
+    // prove that boards differ, but matching ones need a full comparison to be sure.
+    pub fn checksum(&self) -> u64 {
+        return self.cells.iter()
+            .flatten()
+            .fold(0u64, |acc, &c| acc.wrapping_mul(31).wrapping_add(c as u64));
+    }
+
+    // A 128 bit FNV-1a hash of the board, which unlike checksum is long enough to stand in for the
+    // board itself: a collision between two of even billions of boards is vanishingly unlikely.
+    // It's the same from one run, and one build, to the next, so it can be saved.
+    pub fn content_hash(&self) -> u128 {
+        const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+        const PRIME: u128 = 0x0000000001000000000000000000013b;
+        let (rows, cols) = self.size();
+        let chars = [rows as u32, cols as u32].into_iter()
+            .chain(self.cells.iter().flatten().map(|&c| c as u32));
+        return chars
+            .flat_map(u32::to_le_bytes)
+            .fold(OFFSET, |acc, byte| (acc ^ byte as u128).wrapping_mul(PRIME));
+    }
+
+    // Every real waffle is, but nothing stops a board from being read in with a different number of
+    // rows and columns.
+    pub fn is_square(&self) -> bool {
+        let (rows, cols) = self.size();
+        return rows == cols;
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        return (self.cells.len(), self.cells[0].len());
+    }
+
+    pub fn layout(&self) -> WaffleLayout {
+        let (rows, cols) = self.size();
+        return WaffleLayout::new(rows, cols);
+    }
+
+    // The cells, row by row. Use set, set_row, or set_col to change them.
+    pub fn cells(&self) -> &[Vec<char>] {
+        return &self.cells;
+    }
+
+    pub fn get(&self, coord: Coord) -> char {
+        return self.cells[coord.row][coord.col];
+    }
+
+    // Checks that the two boards can be compared: that they're the same size, with their gaps in
+    // the same places. Boards read in by this crate always have their gaps where a waffle's go,
+    // but anything can be put in cells, so this looks rather than assuming.
+    pub fn check_same_layout(&self, other: &Self) -> Result<(), BoardError> {
+        if self.size() != other.size() {
+            return Err(BoardError::SizeMismatch { first: self.size(), second: other.size() });
+        }
+        let (rows, cols) = self.size();
+        for row in 0..rows {
+            for col in 0..cols {
+                let coord = Coord{ row: row, col: col };
+                if (self.get(coord) == GAP) == (other.get(coord) == GAP) { continue; }
+                return Err(BoardError::GapMismatch { coord: coord });
+            }
+        }
+        return Ok(());
+    }
+
+    // Every cell which isn't a gap, row by row.
+    pub fn active_coords(&self) -> &[Coord] {
+        return &self.active;
+    }
+
+    // The letters which differ between the two boards. Gaps are never compared.
+    pub fn diff(&self, other: &Self) -> Vec<Coord> {
+        let (selfsize, othersize) = (self.size(), other.size());
+        let fmt = |size: (usize, usize)| format!("{}x{}", size.0, size.1);
+        assert!(self.size() == other.size(),
+                "Size mismatch: {} vs {}", fmt(selfsize), fmt(othersize));
+
+        return self.active.iter()
+            .copied()
+            .filter(|&coord| self.get(coord) != other.get(coord))
+            .collect();
+    }
+
+    // Like diff, but with each differing cell's letter here and in the target alongside it.
+    pub fn delta(&self, target: &Self) -> Vec<(Coord, char, char)> {
+        return self.diff(target).into_iter()
+            .map(|coord| (coord, self.get(coord), target.get(coord)))
+            .collect();
+    }
+
+    // How many letters differ between the two boards; gaps never count. Lower is closer, and 0
+    // means the boards are the same.
+    pub fn hamming_distance(&self, other: &Self) -> usize {
+        return self.diff(other).len();
+    }
+
+    // The search's name for hamming_distance: the fewer cells left wrong, the better the board.
+    pub fn score(&self, other: &Self) -> usize {
+        return self.hamming_distance(other);
+    }
+
+    // How many of each letter the board holds, not counting gaps.
+    pub fn letter_counts(&self) -> HashMap<char, usize> {
+        let mut ret = HashMap::new();
+        for &coord in self.active_coords() {
+            *ret.entry(self.get(coord)).or_default() += 1;
+        }
+        return ret;
+    }
+
+    // The fraction of letter cells which already match the target, as a percentage.
+    pub fn solved_percent(&self, other: &Self) -> f64 {
+        let statuses: Vec<CellStatus> = self.match_status(other).into_iter().flatten().collect();
+        let active = statuses.iter().filter(|&&status| status != CellStatus::Gap).count();
+        let correct = statuses.iter().filter(|&&status| status == CellStatus::Correct).count();
+        return 100.0 * correct as f64 / active as f64;
+    }
+
+    // Whether each cell matches the target. Unlike the game's colouring, there's no notion of a
+    // letter being in the right word but the wrong place.
+    pub fn match_status(&self, target: &Self) -> Vec<Vec<CellStatus>> {
+        let (rows, cols) = self.size();
+        return (0..rows)
+            .map(|row| (0..cols)
+                .map(|col| Coord{ row: row, col: col })
+                .map(|coord| (self.layout().is_gap(coord), self.get(coord) == target.get(coord)))
+                .map(|status| match status {
+                    (true, _) => CellStatus::Gap,
+                    (false, true) => CellStatus::Correct,
+                    (false, false) => CellStatus::Wrong,
+                })
+                .collect())
+            .collect();
+    }
+
+    // Every row and column at an even index holds a word. Returns the coordinates of each word's
+    // cells in reading order: the rows from top to bottom, then the columns from left to right.
+    pub fn word_cells(&self) -> Vec<Vec<Coord>> {
+        return self.layout().word_cells();
+    }
+
+    // The same as word_cells, but with a name for each word (eg "row 0").
+    pub fn words(&self) -> Vec<(String, Vec<Coord>)> {
+        let row_words = self.size().0.div_ceil(2);
+        return self.word_cells().into_iter()
+            .enumerate()
+            .map(|(i, cells)| match i < row_words {
+                true => (format!("row {}", cells[0].row), cells),
+                false => (format!("col {}", cells[0].col), cells),
+            })
+            .collect();
+    }
+
+    // The names of the words (as in words) which are the same here as in the target.
+    pub fn complete_words(&self, target: &Self) -> Vec<String> {
+        return self.words().into_iter()
+            .filter(|(_, cells)| cells.iter().all(|&coord| self.get(coord) == target.get(coord)))
+            .map(|(name, _)| name)
+            .collect();
+    }
+
+    // Builds a board of the given size, taking each cell from wherever source says it comes from.
+    fn remapped(&self, size: (usize, usize), source: impl Fn(usize, usize) -> Coord) -> Self {
+        let (rows, cols) = size;
+        return Self::with_cells((0..rows)
+            .map(|row| (0..cols).map(|col| self.get(source(row, col))).collect())
+            .collect());
+    }
+
+    // The rotations and reflections only keep every gap where a waffle's belong, and every word
+    // the same length, when the board is square.
+    fn check_square(&self) -> Result<(), BoardError> {
+        let (rows, cols) = self.size();
+        if !self.is_square() { return Err(BoardError::NotSquare { rows: rows, cols: cols }); }
+        return Ok(());
+    }
+
+    // The board turned a quarter turn clockwise.
+    pub fn rotate90(&self) -> Result<Self, BoardError> {
+        self.check_square()?;
+        let (rows, cols) = self.size();
+        return Ok(self.remapped((cols, rows), |row, col| Coord{ row: rows - 1 - col, col: row }));
+    }
+
+    pub fn rotate180(&self) -> Result<Self, BoardError> {
+        self.check_square()?;
+        let (rows, cols) = self.size();
+        return Ok(self.remapped((rows, cols), |row, col| {
+            Coord{ row: rows - 1 - row, col: cols - 1 - col }
+        }));
+    }
+
+    // Mirrors the board left to right.
+    pub fn flip_horizontal(&self) -> Result<Self, BoardError> {
+        self.check_square()?;
+        let (rows, cols) = self.size();
+        return Ok(self.remapped((rows, cols), |row, col| Coord{ row: row, col: cols - 1 - col }));
+    }
+
+    // Mirrors the board top to bottom.
+    pub fn flip_vertical(&self) -> Result<Self, BoardError> {
+        self.check_square()?;
+        let (rows, cols) = self.size();
+        return Ok(self.remapped((rows, cols), |row, col| Coord{ row: rows - 1 - row, col: col }));
+    }
+
+    // Mirrors the board across its main diagonal, so that the words across become the words down
+    // and the other way round.
+    pub fn transpose(&self) -> Result<Self, BoardError> {
+        self.check_square()?;
+        let (rows, cols) = self.size();
+        return Ok(self.remapped((cols, rows), |row, col| Coord{ row: col, col: row }));
+    }
+
+    // Whether this could be a solved waffle: the gaps are all in the right places, and every word
+    // is in the dictionary. Letters are compared in lowercase.
+    pub fn is_valid_waffle(&self, dict: &Dictionary) -> bool {
+        if self.validate().is_err() { return false; }
+        return self.word_cells().iter().all(|cells| {
+            let word: String = cells.iter()
+                .map(|&coord| self.get(coord).to_ascii_lowercase())
+                .collect();
+            dict.contains(&word)
+        });
+    }
+
+    pub fn display(&self) -> String {
+        return self.cells.iter()
+            .map(|row| row.iter().collect::<String>())
+            .join("\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::fixtures::{board, loyal, swaps};
+
+    #[test]
+    fn swapping_a_gap_is_an_error() {
+        let loyal = loyal();
+        let swap = swaps("1,1-2,2")[0];
+        let gap = Coord{ row: 1, col: 1 };
+        assert_eq!(loyal.try_swap(swap), Err(BoardError::GapSwap { coord: gap }));
+        assert!(loyal.try_apply_all(&swaps("0,0-0,4 3,3-4,4")).is_err());
+    }
+
+    #[test]
+    fn coords_are_written_in_each_style() {
+        let coord = Coord{ row: 2, col: 1 };
+        assert_eq!(coord.format(CoordStyle::Zero), "(2,1)");
+        assert_eq!(coord.format(CoordStyle::One), "(3,2)");
+        assert_eq!(coord.format(CoordStyle::A1), "B3");
+        // Past Z the columns carry on as AA, AB, and so on.
+        assert_eq!(Coord{ row: 0, col: 26 }.format(CoordStyle::A1), "AA1");
+        assert_eq!(CoordStyle::parse("a1"), Some(CoordStyle::A1));
+        assert_eq!(CoordStyle::parse("A1"), None);
+    }
+
+    #[test]
+    fn boards_with_their_gaps_in_different_places_cannot_be_compared() {
+        let loyal = loyal();
+        // Nothing read in can look like this, but a board can still be made of any cells.
+        let gapless = WaffleBoard::with_cells(["loyal", "iquro", "locus", "axcye", "chair"].iter()
+            .map(|row| row.chars().collect())
+            .collect());
+        let mismatch = Err(BoardError::GapMismatch { coord: Coord{ row: 1, col: 1 } });
+        assert_eq!(gapless.check_same_layout(&loyal), mismatch);
+        assert_eq!(loyal.check_same_layout(&gapless), mismatch);
+        assert_eq!(gapless.check_same_layout(&gapless), Ok(()));
+        let swapped = loyal.swap(Swap::new(Coord{ row: 0, col: 0 }, Coord{ row: 4, col: 4 }));
+        assert_eq!(loyal.check_same_layout(&swapped), Ok(()));
+
+        let short = board("
+            loyal
+            i u o
+            locus
+        ");
+        assert_eq!(short.check_same_layout(&loyal),
+                   Err(BoardError::SizeMismatch { first: (3, 5), second: (5, 5) }));
+    }
+
+    #[test]
+    fn byte_order_marks_and_leading_blank_lines_are_skipped() {
+        let clean = "loyal\ni u o\nlocus\na c e\nchair\n";
+        for messy in [format!("\u{feff}{}", clean), format!("\n  \n{}", clean),
+                      format!("\u{feff}\r\n\n{}", clean)] {
+            assert_eq!(messy.parse::<WaffleBoard>(), Ok(loyal()), "{:?}", messy);
+            assert_eq!(board_lines(&messy).collect::<Vec<&str>>(),
+                       board_lines(clean).collect::<Vec<&str>>(), "{:?}", messy);
+        }
+        // Only the start is cleaned up, so a mark anywhere else is still a tile.
+        let late = "loyal\ni u o\n\u{feff}ocus\na c e\nchair";
+        assert_eq!(late.parse::<WaffleBoard>().unwrap().get(Coord{ row: 2, col: 0 }), '\u{feff}');
+    }
+
+    #[test]
+    fn swaps_read_back_what_they_write() {
+        let swap = Swap::new(Coord{ row: 4, col: 2 }, Coord{ row: 0, col: 10 });
+        assert_eq!(swap.to_string(), "0,10-4,2");
+        assert_eq!(swap.to_string().parse(), Ok(swap));
+        // The cells may come in either order, with space around the numbers.
+        assert_eq!(" 4, 2 - 0,10 ".parse(), Ok(swap));
+
+        for bad in ["", "0,0", "0,0-", "0,0-1", "0,0-1,x", "-1,0-2,2", "0;0-2,2", "0,0-2,2-4,4"] {
+            let invalid = BoardError::InvalidSwap { text: bad.to_owned() };
+            assert_eq!(bad.parse::<Swap>(), Err(invalid), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn boards_without_the_waffle_layout_are_rejected() {
+        let letter_in_gap = "loyal\nixu o\nlocus\na c e\nchair".parse::<WaffleBoard>();
+        let found = BoardError::MalformedWaffle { coord: Coord{ row: 1, col: 1 }, found: 'x' };
+        assert_eq!(letter_in_gap, Err(found));
+
+        let gap_in_word = "loyal\ni u o\nlo us\na c e\nchair".parse::<WaffleBoard>();
+        let found = BoardError::MalformedWaffle { coord: Coord{ row: 2, col: 2 }, found: ' ' };
+        assert_eq!(gap_in_word, Err(found));
+    }
+
+    #[test]
+    fn word_cells_are_the_rows_then_the_columns() {
+        let loyal = loyal();
+        let row = |row| (0..5).map(|col| Coord{ row: row, col: col }).collect::<Vec<_>>();
+        let col = |col| (0..5).map(|row| Coord{ row: row, col: col }).collect::<Vec<_>>();
+        assert_eq!(loyal.word_cells(), vec![row(0), row(2), row(4), col(0), col(2), col(4)]);
+        let spelled: Vec<String> = loyal.word_cells().iter()
+            .map(|cells| cells.iter().map(|&coord| loyal.get(coord)).collect())
+            .collect();
+        assert_eq!(spelled, ["loyal", "locus", "chair", "lilac", "yucca", "loser"]);
+    }
+
+    #[test]
+    fn standard_layout_has_21_tiles_in_6_words() {
+        let layout = WaffleLayout::standard_5x5();
+        assert_eq!(loyal().layout(), layout);
+        assert_eq!(layout.active_cells().len(), 21);
+        let coords = |cells: &[(usize, usize)]| -> Vec<Coord> {
+            return cells.iter().map(|&(row, col)| Coord{ row: row, col: col }).collect();
+        };
+        let gaps: Vec<Coord> = layout.coords().filter(|&coord| layout.is_gap(coord)).collect();
+        assert_eq!(gaps, coords(&[(1, 1), (1, 3), (3, 1), (3, 3)]));
+        assert!(!layout.is_gap(Coord{ row: 5, col: 5 }));
+
+        let words = layout.word_cells();
+        assert_eq!(words.len(), 6);
+        assert_eq!(words[1], coords(&[(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]));
+        assert_eq!(words[5], coords(&[(0, 4), (1, 4), (2, 4), (3, 4), (4, 4)]));
+        assert!(words.iter().flatten().all(|&coord| !layout.is_gap(coord)));
+
+        let deluxe = WaffleLayout::deluxe_7x7();
+        assert_eq!(deluxe.active_cells().len(), 40);
+        assert_eq!(deluxe.word_cells().len(), 8);
+    }
+
+    #[test]
+    fn boards_are_built_from_their_words_when_they_agree() {
+        let words = "row0=loyal, row2=locus, row4=chair, col0=lilac, col2=yucca, col4=loser";
+        assert_eq!(WaffleBoard::from_words(words), Ok(loyal()));
+        // Any order and any separators will do.
+        assert_eq!(WaffleBoard::from_words("col4=loser row4=chair\ncol0=lilac,,row0=loyal \
+                                            col2=yucca row2=locus"), Ok(loyal()));
+
+        let conflict = words.replace("col2=yucca", "col2=bucca");
+        assert_eq!(WaffleBoard::from_words(&conflict), Err(BoardError::ConflictingLetters {
+            coord: Coord{ row: 0, col: 2 }, first: 'y', second: 'b',
+        }));
+        let missing = words.replace(", col2=yucca", "");
+        assert_eq!(WaffleBoard::from_words(&missing),
+                   Err(BoardError::MissingLetter { coord: Coord{ row: 1, col: 2 } }));
+        for bad in ["row1=abcde", "row0=loyal row2=loc", "row9=loyal", "diag0=loyal", "loyal"] {
+            assert!(matches!(WaffleBoard::from_words(bad),
+                             Err(BoardError::InvalidAssignment { .. })), "{}", bad);
+        }
+        assert_eq!(WaffleBoard::from_words(" , "), Err(BoardError::Empty));
+    }
+
+    #[test]
+    fn with_cell_changes_exactly_one_letter() {
+        let loyal = loyal();
+        let changed = loyal.with_cell(Coord{ row: 2, col: 4 }, 'z').unwrap();
+        assert_eq!(changed, board("
+            loyal
+            i u o
+            locuz
+            a c e
+            chair
+        "));
+        assert_eq!(loyal.get(Coord{ row: 2, col: 4 }), 's');
+
+        assert_eq!(loyal.with_cell(Coord{ row: 5, col: 0 }, 'z'), None);
+        assert_eq!(loyal.with_cell(Coord{ row: 1, col: 1 }, 'z'), None);
+        assert_eq!(loyal.with_cell(Coord{ row: 0, col: 0 }, ' '), None);
+        assert_eq!(loyal.with_cell(Coord{ row: 0, col: 0 }, '.'), None);
+    }
+
+    #[test]
+    fn cells_and_whole_words_change_in_place() {
+        let mut board = loyal();
+        board.set(Coord{ row: 0, col: 0 }, 'r').unwrap();
+        board.set(Coord{ row: 3, col: 4 }, 'a').unwrap();
+        board.set_row(4, "chaos").unwrap();
+        assert_eq!(board.display(), "royal\ni u o\nlocus\na c a\nchaos");
+        board.set_col(2, "yucca").unwrap();
+        assert_eq!(board.get(Coord{ row: 4, col: 2 }), 'a');
+
+        // Nothing which would break the board is written.
+        let gap = Coord{ row: 1, col: 1 };
+        assert_eq!(board.set(gap, 'z'), Err(BoardError::MalformedWaffle { coord: gap, found: 'z' }));
+        let cell = Coord{ row: 0, col: 0 };
+        assert_eq!(board.set(cell, ' '), Err(BoardError::MalformedWaffle { coord: cell, found: ' ' }));
+        let off = Coord{ row: 5, col: 0 };
+        assert_eq!(board.set(off, 'z'), Err(BoardError::OffBoard { coord: off }));
+        assert!(board.set_row(1, "abcde").is_err());
+        assert!(board.set_row(0, "abcd").is_err());
+        assert!(board.set_col(0, "ab.de").is_err());
+        assert_eq!(board.display(), "royal\ni u o\nlocus\na c a\nchaos");
+        assert_eq!(board.validate(), Ok(()));
+    }
+
+    #[test]
+    fn checksums_match_for_equal_boards_and_rarely_otherwise() {
+        let loyal = loyal();
+        assert_eq!(loyal.checksum(), loyal.clone().checksum());
+        // Swapping the two l's makes an equal board out of different moves.
+        let same = loyal.swap(Swap::new(Coord{ row: 0, col: 0 }, Coord{ row: 0, col: 4 }));
+        assert_eq!(same.checksum(), loyal.checksum());
+
+        // Every board up to two swaps from the answer.
+        let coords = loyal.active_coords().to_vec();
+        let pairs: Vec<Swap> = coords.iter().enumerate()
+            .flat_map(|(i, &a)| coords[i + 1..].iter().map(move |&b| Swap::new(a, b)))
+            .collect();
+        let boards: HashSet<WaffleBoard> = pairs.iter()
+            .flat_map(|&first| pairs.iter().map(move |&second| (first, second)))
+            .map(|(first, second)| loyal.swap(first).swap(second))
+            .collect();
+        let checksums: HashSet<u64> = boards.iter().map(WaffleBoard::checksum).collect();
+        assert!(boards.len() > 10000, "{}", boards.len());
+        assert_eq!(checksums.len(), boards.len());
+    }
+
+    #[test]
+    fn cached_active_cells_match_the_letters_on_each_board() {
+        let fresh = |board: &WaffleBoard| -> Vec<Coord> {
+            let (rows, cols) = board.size();
+            return (0..rows)
+                .flat_map(|row| (0..cols).map(move |col| Coord{ row: row, col: col }))
+                .filter(|&coord| board.get(coord) != GAP)
+                .collect();
+        };
+        let loyal = loyal();
+        let mut edited = loyal.clone();
+        edited.set_row(0, "royal").unwrap();
+        let boards = [
+            loyal.clone(),
+            loyal.swap(Swap::new(Coord{ row: 0, col: 0 }, Coord{ row: 4, col: 4 })),
+            loyal.rotate90().unwrap(),
+            "LOYAL\ni.u.o\nLOCUS\na.c.e\nCHAIR".parse::<WaffleBoard>().unwrap().normalized(),
+            loyal.with_cell(Coord{ row: 2, col: 2 }, 'z').unwrap(),
+            edited,
+            WaffleBoard::from_words("row0=loyal row2=locus row4=chair col0=lilac col2=yucca \
+                                     col4=loser").unwrap(),
+            board("
+                abcdefg
+                h i j k
+                lmnopqr
+                s t u v
+                wxyzabc
+                d e f g
+                hijklmn
+            "),
+            board("
+                loyal
+                i u o
+                locus
+            "),
+        ];
+        for board in &boards {
+            assert_eq!(board.active_coords(), fresh(board), "{}", board.display());
+            assert_eq!(board.active_coords(), board.layout().active_cells(), "{}", board.display());
+        }
+        assert_eq!(boards[7].active_coords().len(), 40);
+        assert_eq!(boards[8].active_coords().len(), 13);
+    }
+
+    #[test]
+    fn normalizing_ignores_case_and_how_gaps_are_written() {
+        let shouted = board("
+            LOYAL
+            I.U.O
+            LoCuS
+            a c e
+            CHAIR
+        ");
+        let loyal = board("
+            loyal
+            i u o
+            locus
+            a.c.e
+            chair
+        ");
+        assert_ne!(shouted, loyal);
+        assert_eq!(shouted.normalized(), loyal.normalized());
+        assert_eq!(shouted.normalized(), loyal);
+        let swapped = loyal.swap(Swap::new(Coord{ row: 0, col: 0 }, Coord{ row: 0, col: 1 }));
+        assert_ne!(shouted.normalized(), swapped);
+    }
+
+    #[test]
+    fn match_status_marks_each_cell_correct_wrong_or_gap() {
+        let loyal = loyal();
+        let (correct, wrong, gap) = (CellStatus::Correct, CellStatus::Wrong, CellStatus::Gap);
+        let solved = vec![correct, gap, correct, gap, correct];
+        assert_eq!(loyal.match_status(&loyal), vec![
+            vec![correct; 5], solved.clone(), vec![correct; 5], solved, vec![correct; 5],
+        ]);
+        assert_eq!(loyal.solved_percent(&loyal), 100.0);
+
+        let swapped = loyal.swap(Swap::new(Coord{ row: 0, col: 0 }, Coord{ row: 1, col: 2 }));
+        let statuses = swapped.match_status(&loyal);
+        assert_eq!(statuses[0], vec![wrong, correct, correct, correct, correct]);
+        assert_eq!(statuses[1], vec![correct, gap, wrong, gap, correct]);
+    }
+
+    #[test]
+    fn delta_lists_each_differing_cell_with_both_letters() {
+        let loyal = loyal();
+        let from = board("
+            coyal
+            i u o
+            locus
+            a l e
+            chair
+        ");
+        let coord = |row, col| Coord{ row: row, col: col };
+        assert_eq!(from.delta(&loyal), vec![(coord(0, 0), 'c', 'l'), (coord(3, 2), 'l', 'c')]);
+        assert_eq!(loyal.delta(&from), vec![(coord(0, 0), 'l', 'c'), (coord(3, 2), 'c', 'l')]);
+        assert_eq!(loyal.delta(&loyal), Vec::new());
+    }
+
+    #[test]
+    fn rotations_and_reflections_undo_themselves() {
+        let loyal = loyal();
+        let turn = |board: &WaffleBoard| board.rotate90().unwrap();
+        let half = |board: &WaffleBoard| board.rotate180().unwrap();
+        let across = |board: &WaffleBoard| board.flip_horizontal().unwrap();
+        let down = |board: &WaffleBoard| board.flip_vertical().unwrap();
+        // Clockwise, so the first column read upwards becomes the first row.
+        assert_eq!(turn(&loyal), board("
+            calil
+            h o o
+            accuy
+            i u a
+            resol
+        "));
+        assert_eq!(turn(&turn(&turn(&turn(&loyal)))), loyal);
+        assert_eq!(turn(&turn(&loyal)), half(&loyal));
+        assert_eq!(half(&half(&loyal)), loyal);
+        assert_eq!(across(&across(&loyal)), loyal);
+        assert_eq!(down(&down(&loyal)), loyal);
+        assert_eq!(down(&across(&loyal)), half(&loyal));
+        assert_eq!(across(&loyal).cells()[0], ['l', 'a', 'y', 'o', 'l']);
+    }
+
+    #[test]
+    fn square_only_operations_reject_other_boards() {
+        let loyal = loyal();
+        assert_eq!(loyal.transpose().unwrap().word_cells().len(), 6);
+        assert_eq!(loyal.transpose().unwrap().transpose(), Ok(loyal.clone()));
+
+        let short = board("
+            loyal
+            i u o
+            locus
+        ");
+        let not_square = Err(BoardError::NotSquare { rows: 3, cols: 5 });
+        assert!(!short.is_square());
+        assert_eq!(short.transpose(), not_square);
+        assert_eq!(short.rotate90(), not_square);
+        assert_eq!(short.rotate180(), not_square);
+        assert_eq!(short.flip_horizontal(), not_square);
+        assert_eq!(short.flip_vertical(), not_square);
+    }
+
+    #[test]
+    fn a_valid_waffle_has_a_word_in_every_row_and_column() {
+        let dict = Dictionary::new(["loyal", "locus", "chair", "lilac", "yucca", "loser"]
+            .into_iter()
+            .map(str::to_owned));
+        let loyal = board("
+            LOYAL
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        assert!(loyal.is_valid_waffle(&dict));
+        // Neither the o nor the u in the middle row is in a column, so only that row stops being
+        // a word.
+        let lucos = board("
+            loyal
+            i u o
+            lucos
+            a c e
+            chair
+        ");
+        assert!(!lucos.is_valid_waffle(&dict));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json_round_trips() {
+        let loyal = loyal();
+        let json = loyal.to_json();
+        let first_rows = r#"{"cells":[["l","o","y","a","l"],["i"," ","u"," ","o"],"#;
+        assert!(json.starts_with(first_rows), "{}", json);
+        assert_eq!(WaffleBoard::from_json(&json).unwrap(), loyal);
+        // The layout is checked, as it is for text.
+        let broken = json.replacen(r#"["i"," ","#, r#"["i","x","#, 1);
+        assert!(WaffleBoard::from_json(&broken).is_err());
+    }
+}
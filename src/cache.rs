@@ -0,0 +1,136 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Nothing in the command line tools solves the same puzzle twice, so this is only of use to things
+// embedding the solver, such as a service answering the daily puzzle for many people.
+
+use std::collections::HashMap;
+
+use crate::board::{Swap, WaffleBoard};
+use crate::heuristic::DiffHalf;
+use crate::profile::Profile;
+use crate::search::{find_swaps, SearchOptions};
+
+// A hash of the pair of boards which is stable across runs and builds (unlike DefaultHasher), so
+// it can be used as a key for storage that outlives the process. This is 64-bit FNV-1a.
+pub fn puzzle_hash(from: &WaffleBoard, into: &WaffleBoard) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |c: char| {
+        for byte in (c as u32).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+
+    for board in [from, into] {
+        for row in board.cells() {
+            row.iter().copied().for_each(&mut feed);
+            feed('\n');
+        }
+        // Separate the boards with something that can't appear in either of them.
+        feed('\0');
+    }
+    return hash;
+}
+
+// Somewhere to keep solutions which have already been found, keyed by puzzle_hash.
+pub trait SolveStore {
+    fn get(&self, key: u64) -> Option<Vec<Swap>>;
+    fn put(&mut self, key: u64, value: Vec<Swap>);
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    solutions: HashMap<u64, Vec<Swap>>,
+}
+
+impl SolveStore for MemoryStore {
+    fn get(&self, key: u64) -> Option<Vec<Swap>> {
+        return self.solutions.get(&key).cloned();
+    }
+
+    fn put(&mut self, key: u64, value: Vec<Swap>) {
+        self.solutions.insert(key, value);
+    }
+}
+
+// Like find_swaps, but checks the store first, and saves any solution it finds there.
+pub fn find_swaps_cached(from: &WaffleBoard, into: &WaffleBoard,
+                         store: &mut dyn SolveStore) -> Option<Vec<Swap>> {
+    let key = puzzle_hash(from, into);
+    if let Some(steps) = store.get(key) { return Some(steps); }
+
+    let options = SearchOptions::default();
+    let steps = find_swaps(from, into, &options, DiffHalf, &mut Profile::new(false))?;
+    store.put(key, steps.clone());
+    return Some(steps);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::fixtures::{board, swaps};
+
+    // Counts how often the solver goes to the store and what it finds there.
+    #[derive(Default)]
+    struct CountingStore {
+        store: MemoryStore,
+        hits: Cell<usize>,
+        puts: usize,
+    }
+
+    impl SolveStore for CountingStore {
+        fn get(&self, key: u64) -> Option<Vec<Swap>> {
+            let ret = self.store.get(key);
+            if ret.is_some() { self.hits.set(self.hits.get() + 1); }
+            return ret;
+        }
+
+        fn put(&mut self, key: u64, value: Vec<Swap>) {
+            self.puts += 1;
+            self.store.put(key, value);
+        }
+    }
+
+    #[test]
+    fn solving_the_same_puzzle_twice_hits_the_store() {
+        let from = board("
+            oylal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        let into = board("
+            loyal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        let mut store = CountingStore::default();
+        let first = find_swaps_cached(&from, &into, &mut store).unwrap();
+        assert_eq!((store.hits.get(), store.puts), (0, 1));
+        let second = find_swaps_cached(&from, &into, &mut store).unwrap();
+        assert_eq!((store.hits.get(), store.puts), (1, 1));
+        assert_eq!(first, second);
+
+        // Whatever is stored is returned as it is, without solving the puzzle again.
+        let mut planted = MemoryStore::default();
+        planted.put(puzzle_hash(&from, &into), swaps("0,0-4,4"));
+        assert_eq!(find_swaps_cached(&from, &into, &mut planted), Some(swaps("0,0-4,4")));
+        assert_ne!(puzzle_hash(&from, &into), puzzle_hash(&into, &from));
+    }
+}
@@ -0,0 +1,502 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, fs, io};
+
+use crate::board::{board_lines, is_gap_char, BoardError, Coord, WaffleLayout};
+use crate::dictionary::Dictionary;
+
+// Letters are matched regardless of case, so are stored in lowercase. Symbols are opaque, and kept
+// exactly as they are. Anything which isn't a tile is an error, rather than being quietly dropped.
+fn fold_tile(c: char, opaque: bool) -> Result<char, BoardError> {
+    if !is_tile(c, opaque) { return Err(BoardError::UnsupportedChar { ch: c }); }
+    return Ok(if opaque { c } else { c.to_ascii_lowercase() });
+}
+
+// Which characters are tiles. Letters are limited to ASCII, which also rules out the few letters
+// whose lowercase is more than one character.
+fn is_tile(c: char, opaque: bool) -> bool {
+    return if opaque { !is_gap_char(c) && !c.is_whitespace() } else { c.is_ascii_alphanumeric() };
+}
+
+fn cell_index(cell: usize) -> Option<usize> {
+    return match cell & 1usize {
+        0 => Some(cell / 2),
+        1 => None,
+        _ => panic!("Something went horribly wrong!"),
+    };
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Constraint {
+    constraints: HashMap<usize, char>,
+}
+
+impl Constraint {
+    pub fn new() -> Self {
+        return Self { constraints: HashMap::new() };
+    }
+
+    pub fn from(pattern: &str) -> Self {
+        let mut constraints = HashMap::new();
+
+        for (i, c) in pattern.chars().enumerate() {
+            if c == '?' { continue; }
+            constraints.insert(i, c);
+        }
+
+        return Self {
+            constraints: constraints,
+        };
+    }
+
+    pub fn with(&self, idx: usize, val: char) -> Self {
+        let mut constraints = self.constraints.clone();
+        constraints.insert(idx, val);
+        return Self { constraints };
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        for (i, c) in word.chars().enumerate() {
+            if let Some(&expected) = self.constraints.get(&i) {
+                if expected != c { return false; }
+            }
+        }
+
+        return true;
+    }
+
+    pub fn get(&self, index: usize) -> Option<char> {
+        return self.constraints.get(&index).copied();
+    }
+
+    pub fn num_set(&self) -> usize { return self.constraints.len(); }
+
+    // Every letter which some word matching the constraint has at idx. The constraint doesn't know
+    // how long its word is, so any word long enough counts; pass a dictionary of words of the
+    // right length alone for an exact answer.
+    pub fn possible_letters(&self, idx: usize, dict: &Dictionary) -> HashSet<char> {
+        return dict.words().into_iter()
+            .filter(|word| self.matches(word))
+            .filter_map(|word| word.chars().nth(idx))
+            .collect();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstraintBoard {
+    rows: Vec<Constraint>,
+    cols: Vec<Constraint>,
+    unused: HashMap<char, usize>,
+    // Whether the tiles are opaque symbols rather than letters, so case means nothing.
+    opaque: bool,
+}
+
+impl ConstraintBoard {
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        return Ok(fs::read_to_string(path)?.parse()?);
+    }
+
+    // Reads a board of opaque symbols, such as digits, which have no case to mark the known cells
+    // with. Those are instead marked with a 'g' in known, a grid of the same shape, as for the
+    // colours given to waffle auto. The word list should then be made of the same symbols.
+    pub fn from_symbols(tiles: &str, known: &str) -> Result<Self, BoardError> {
+        let known: Vec<Vec<char>> = board_lines(known).map(|line| line.chars().collect()).collect();
+        let tile_lines: Vec<&str> = board_lines(tiles).collect();
+        if known.len() != tile_lines.len() { return Err(BoardError::UnevenLines); }
+        for (known_line, tile_line) in known.iter().zip(&tile_lines) {
+            if known_line.len() != tile_line.chars().count() {
+                return Err(BoardError::UnevenLines);
+            }
+        }
+        return Self::build(&tile_lines.join("\n"), true, &|row, col, _| known[row][col] == 'g');
+    }
+
+    // Reads the board, counting up every tile, then places the ones is_known picks out.
+    fn build(text: &str, opaque: bool,
+             is_known: &dyn Fn(usize, usize, char) -> bool) -> Result<Self, BoardError> {
+        let cells: Vec<Vec<char>> = text
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+
+        if cells.len() == 0 { return Err(BoardError::Empty); }
+        let len = cells[0].len();
+        if !cells.iter().all(|line| line.len() == len) { return Err(BoardError::UnevenLines); }
+        // Words are only kept for as many rows as there are columns, so any more would be lost.
+        if cells.len() != len { return Err(BoardError::NotSquare { rows: cells.len(), cols: len }); }
+
+        let layout = WaffleLayout::new(len, len);
+        for (row, rowchars) in cells.iter().enumerate() {
+            for (col, &cell) in rowchars.iter().enumerate() {
+                let coord = Coord{ row: row, col: col };
+                if layout.is_gap(coord) == is_gap_char(cell) { continue; }
+                return Err(BoardError::MalformedWaffle { coord: coord, found: cell });
+            }
+        }
+
+        let mut chars: HashMap<char, usize> = HashMap::new();
+        for &c in cells.iter().flatten().filter(|&&c| !is_gap_char(c)) {
+            *chars.entry(fold_tile(c, opaque)?).or_default() += 1;
+        }
+
+        let mut ret = Self {
+            rows: vec![Constraint::new(); len / 2 + 1],
+            cols: vec![Constraint::new(); len / 2 + 1],
+            unused: chars,
+            opaque: opaque,
+        };
+
+        for (row, rowchars) in cells.iter().enumerate() {
+            for (col, &cell) in rowchars.iter().enumerate() {
+                if is_gap_char(cell) || !is_known(row, col, cell) { continue; }
+                ret = match ret.with(row, col, fold_tile(cell, opaque)?) {
+                    Some(board) => board,
+                    None => panic!("Invalid board"),
+                };
+            }
+        }
+
+        return Ok(ret);
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<char> {
+        if let Some(row_idx) = cell_index(row) {
+            return self.rows[row_idx].get(col);
+        }
+        if let Some(col_idx) = cell_index(col) {
+            return self.cols[col_idx].get(row);
+        }
+        return None;
+    }
+
+    pub fn with(&self, row: usize, col: usize, val: char) -> Option<Self> {
+        // First check if the cell is already set. If it is, and it's set to what we're setting it
+        // to, we can just do nothing. If there's a mismatch, that's an error. We're not handling
+        // the error at this point, as for now that's just considered a caller error. That's a good
+        // candidate for something to change later on.
+        if let Some(cur) = self.get(row, col) {
+            if cur == val { return Some(self.clone()); }
+            panic!("Cannot set ({}, {}) to {}: Already set to {}", row, col, val, cur);
+        }
+
+        // If we get here, the cell is empty. Check if we even have the budget for this new
+        // character. If we do, find how many we had left over and subtract one, that's how many of
+        // this character we have now.
+        let remaining = match self.unused.get(&val) {
+            None => return None,
+            Some(0) => panic!("Invalid board!"),
+            Some(&remainder) => remainder,
+        } - 1;
+
+        // Copy the fields.
+        let mut rows = self.rows.clone();
+        let mut cols = self.cols.clone();
+        let mut unused = self.unused.clone();
+
+        if remaining == 0 {
+            unused.remove(&val);
+        } else {
+            *unused.get_mut(&val).unwrap() -= 1;
+        }
+
+        if let Some(constraint_row) = cell_index(row) {
+            let row = rows.get_mut(constraint_row).unwrap();
+            *row = row.with(col, val);
+        }
+        if let Some(constraint_col) = cell_index(col) {
+            let col = cols.get_mut(constraint_col).unwrap();
+            *col = col.with(row, val);
+        }
+
+        return Some(Self {
+            rows: rows,
+            cols: cols,
+            unused: unused,
+            opaque: self.opaque,
+        });
+    }
+
+    // Fills in a word given as =WORD@row<n> or =WORD@col<n>.
+    fn locked(&self, lock: &str) -> Result<Self, BoardError> {
+        let invalid = || BoardError::InvalidLock { text: lock.to_owned() };
+        let (word, slot) = lock[1..].split_once('@').ok_or_else(invalid)?;
+        let (is_row, index) = match (slot.strip_prefix("row"), slot.strip_prefix("col")) {
+            (Some(index), _) => (true, index),
+            (_, Some(index)) => (false, index),
+            _ => return Err(invalid()),
+        };
+        let index: usize = index.parse().map_err(|_| invalid())?;
+        let len = self.word_len();
+        if index >= len || cell_index(index).is_none() || word.chars().count() != len {
+            return Err(invalid());
+        }
+
+        let mut ret = self.clone();
+        for (i, c) in word.chars().enumerate() {
+            let (row, col) = if is_row { (index, i) } else { (i, index) };
+            let c = fold_tile(c, self.opaque)?;
+            // Already filled in with something else, or no more of the letter left to use.
+            if ret.get(row, col).is_some_and(|cur| cur != c) { return Err(invalid()); }
+            ret = ret.with(row, col, c).ok_or_else(invalid)?;
+        }
+        return Ok(ret);
+    }
+
+    // Replaces the letters available for filling in the empty cells, which are normally whichever
+    // letters the board file had left over once the green cells were placed. Each letter in the
+    // rack may be used as many times as it appears there, and letters not in it can't be used.
+    // Whitespace in the rack is ignored, but anything else which isn't a tile is an error.
+    pub fn with_rack(&self, rack: &str) -> Result<Self, BoardError> {
+        let mut unused = HashMap::new();
+        for c in rack.chars().filter(|c| !c.is_whitespace()) {
+            *unused.entry(fold_tile(c, self.opaque)?).or_default() += 1;
+        }
+
+        return Ok(Self {
+            rows: self.rows.clone(),
+            cols: self.cols.clone(),
+            unused: unused,
+            opaque: self.opaque,
+        });
+    }
+
+    // How many of each letter are left to fill in the empty cells with.
+    pub fn unused(&self) -> &HashMap<char, usize> {
+        return &self.unused;
+    }
+
+    pub fn unused_total(&self) -> usize {
+        return self.unused.values().sum();
+    }
+
+    // The length of every word on the board, which is also the width and height of the board.
+    pub fn word_len(&self) -> usize {
+        return self.rows.len() * 2 - 1;
+    }
+
+    // A rough measure of how hard the board is to fill in: the log of how many ways there are to
+    // fill each word, if the words didn't have to agree with each other or the letters left. So 0
+    // means there's nothing left to decide, and a word which nothing fits makes it infinite.
+    pub fn difficulty_estimate(&self, dict: &Dictionary) -> f64 {
+        let words = dict.words();
+        return self.get_all_words().iter()
+            .map(|(constraint, cells)| {
+                words.iter()
+                    .filter(|word| word.chars().count() == cells.len() && constraint.matches(word))
+                    .count()
+            })
+            .map(|count| if count == 0 { f64::INFINITY } else { (count as f64).ln() })
+            .sum();
+    }
+
+    // How many words still have empty cells.
+    pub fn unfilled_words(&self) -> usize {
+        let len = self.word_len();
+        return self.rows.iter().chain(&self.cols)
+            .filter(|constraint| constraint.num_set() != len)
+            .count();
+    }
+
+    pub fn get_all_words(&self) -> Vec<(Constraint, Vec<(usize, usize)>)> {
+        let len = self.word_len();
+        let mut ret = Vec::new();
+
+        for (row, constraint) in self.rows.iter().enumerate() {
+            if constraint.num_set() == len { continue; }
+            let row_idx = row * 2;
+            let cells = (0..len).map(|c| (row_idx, c)).collect();
+            ret.push((constraint.clone(), cells));
+        }
+
+        for (col, constraint) in self.cols.iter().enumerate() {
+            if constraint.num_set() == len { continue; }
+            let col_idx = col * 2;
+            let cells = (0..len).map(|r| (r, col_idx)).collect();
+            ret.push((constraint.clone(), cells));
+        }
+
+        return ret;
+    }
+}
+
+impl FromStr for ConstraintBoard {
+    type Err = BoardError;
+
+    // Uppercase letters are the ones known to be in the right place. Whole words can also be
+    // known, with a line after the board like =TRAIN@row0 or =TABBY@col2, numbering rows and
+    // columns as coordinates do. Their letters are taken from the rest, like the uppercase ones.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (locks, grid): (Vec<&str>, Vec<&str>) = board_lines(text)
+            .partition(|line| line.starts_with('='));
+        let mut ret = Self::build(&grid.join("\n"), false, &|_, _, c| c.is_uppercase())?;
+        for lock in locks { ret = ret.locked(lock)?; }
+        return Ok(ret);
+    }
+}
+
+impl TryFrom<&str> for ConstraintBoard {
+    type Error = BoardError;
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        return text.parse();
+    }
+}
+
+impl fmt::Display for ConstraintBoard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self.word_len();
+        let mut lines = Vec::new();
+        for row in 0..len {
+            let mut cur_row = Vec::new();
+            for col in 0..len {
+                cur_row.push(self.get(row, col).unwrap_or(' '));
+            }
+            lines.push(cur_row.into_iter().collect::<String>());
+        }
+
+        return write!(f, "{}", lines.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::constraint_board;
+
+    // 'İ' lowercases to two characters, so used to hit an assertion, then was quietly dropped.
+    #[test]
+    fn unsupported_letters_are_errors() {
+        let in_board = "\u{130}oyal\ni u o\nlocus\na c e\nchair".parse::<ConstraintBoard>();
+        assert_eq!(in_board.err(), Some(BoardError::UnsupportedChar { ch: '\u{130}' }));
+
+        let locked = "loyal\ni u o\nlocus\na c e\nchair\n=\u{130}OYAL@row0".parse::<ConstraintBoard>();
+        assert_eq!(locked.err(), Some(BoardError::UnsupportedChar { ch: '\u{130}' }));
+
+        let board = constraint_board("
+            loyal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        assert_eq!(board.with_rack("ab?").err(), Some(BoardError::UnsupportedChar { ch: '?' }));
+        assert_eq!(board.with_rack("a b c").map(|board| board.unused().len()), Ok(3));
+    }
+
+    #[test]
+    fn uppercase_letters_are_known_and_the_rest_are_left_to_place() {
+        let board = ConstraintBoard::try_from("LOYAL\ni u o\nlocus\na c e\nchaiR").unwrap();
+        assert_eq!(board.get(0, 0), Some('l'));
+        assert_eq!(board.get(0, 4), Some('l'));
+        assert_eq!(board.get(4, 4), Some('r'));
+        assert_eq!(board.get(2, 0), None);
+        assert_eq!(board.unfilled_words(), 5);
+
+        let expected: HashMap<char, usize> = [
+            ('a', 2), ('c', 3), ('e', 1), ('h', 1), ('i', 2), ('l', 1), ('o', 2), ('s', 1), ('u', 2),
+        ].into_iter().collect();
+        assert_eq!(board.unused(), &expected);
+        assert_eq!(board.unused_total(), 15);
+    }
+
+    #[test]
+    fn boards_with_more_rows_than_columns_are_rejected() {
+        let not_square = Some(BoardError::NotSquare { rows: 7, cols: 5 });
+        // A known letter in the extra rows used to panic.
+        let known = "loyal\ni u o\nlocus\na c e\nchair\nb d f\nghIjk".parse::<ConstraintBoard>();
+        assert_eq!(known.err(), not_square);
+        // Without one, the extra rows used to be dropped without a word.
+        let unknown = "loyal\ni u o\nlocus\na c e\nchair\nb d f\nghijk".parse::<ConstraintBoard>();
+        assert_eq!(unknown.err(), not_square);
+        let symbols = ConstraintBoard::from_symbols("loyal\ni u o\nlocus\na c e\nchair\nb d f\nghijk",
+                                                    "gyyyg\ny y y\nyyyyy\ny y y\nyyyyy\ny y y\nyyyyy");
+        assert_eq!(symbols.err(), not_square);
+    }
+
+    #[test]
+    fn possible_letters_come_from_the_words_which_fit() {
+        let dict = Dictionary::new(["loyal", "local", "locus", "lilac", "royal", "lo"]
+            .map(str::to_owned));
+        let set = |letters: &str| letters.chars().collect::<HashSet<char>>();
+        let lo = Constraint::from("lo???");
+        assert_eq!(lo.possible_letters(2, &dict), set("yc"));
+        assert_eq!(lo.possible_letters(4, &dict), set("ls"));
+        // A known cell can only hold its own letter, if anything fits at all.
+        assert_eq!(lo.possible_letters(0, &dict), set("l"));
+        assert_eq!(Constraint::from("??y??").possible_letters(0, &dict), set("lr"));
+        assert_eq!(Constraint::from("z????").possible_letters(1, &dict), set(""));
+        // With nothing known every word fits, even one too short to fill the board.
+        assert_eq!(Constraint::new().possible_letters(1, &dict), set("oi"));
+    }
+
+    #[test]
+    fn boards_with_more_letters_known_are_easier() {
+        let dict = Dictionary::new(["loyal", "locus", "chair", "lilac", "yucca", "loser", "royal",
+                                    "local", "lilas", "salon", "laser", "hello", "coral"]
+            .map(str::to_owned));
+        let nearly_empty = constraint_board("
+            Loyal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        let mostly_fixed = constraint_board("
+            LOYAL
+            I U O
+            LOCus
+            A C E
+            CHAIR
+        ");
+        let fixed = constraint_board("
+            LOYAL
+            I U O
+            LOCUS
+            A C E
+            CHAIR
+        ");
+        let hard = nearly_empty.difficulty_estimate(&dict);
+        let easy = mostly_fixed.difficulty_estimate(&dict);
+        // Only locus and local still fit the middle row.
+        assert_eq!(easy, 2f64.ln());
+        assert!(easy < hard, "{} >= {}", easy, hard);
+        assert_eq!(fixed.difficulty_estimate(&dict), 0.0);
+    }
+
+    #[test]
+    fn placing_a_word_uses_up_the_letters_it_adds() {
+        let place = |board: &ConstraintBoard, word: &str, cells: &[(usize, usize)]| {
+            return word.chars().zip(cells)
+                .try_fold(board.clone(), |board, (c, &(row, col))| board.with(row, col, c))
+                .unwrap();
+        };
+        let board = constraint_board("
+            Loyal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        assert_eq!(board.unused_total(), 20);
+        let across = place(&board, "locus", &[(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]);
+        assert_eq!(across.unused_total(), 15);
+        assert_eq!(across.unused().get(&'s'), None);
+        // Two of lilac's letters are already on the board, where it crosses loyal and locus.
+        let down = place(&across, "lilac", &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+        assert_eq!(down.unused_total(), 12);
+    }
+}
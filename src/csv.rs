@@ -0,0 +1,33 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Writing CSV for findswaps and waffle batch. Tiles can be any symbol and file names anything at
+// all, so fields holding a comma, a quote, or a line break are quoted, as spreadsheets expect.
+
+pub fn field(text: &str) -> String {
+    if !text.contains([',', '"', '\n', '\r']) { return text.to_owned(); }
+    return format!("\"{}\"", text.replace('"', "\"\""));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_fields_which_need_it_are_quoted() {
+        assert_eq!(field("loyal"), "loyal");
+        assert_eq!(field(","), "\",\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -0,0 +1,165 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::{fs, io};
+
+use crate::constraints::Constraint;
+
+// A word list, one word per line. The order of the words is kept, as the solvers try them in that
+// order, so lists with the most common words first find the likeliest answers first. Each word may
+// be followed by how often it's used, in whatever units the list likes, as long as they're the same
+// throughout.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: Vec<String>,
+    lookup: HashSet<String>,
+    frequencies: HashMap<String, f64>,
+}
+
+impl Dictionary {
+    pub fn new(words: impl IntoIterator<Item = String>) -> Self {
+        let mut ret = Self::default();
+        for word in words {
+            if !ret.lookup.insert(word.clone()) { continue; }
+            ret.words.push(word);
+        }
+        return ret;
+    }
+
+    // Blank lines and surrounding whitespace are ignored.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let mut ret = Self::default();
+        for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let mut fields = line.split_whitespace();
+            let word = match fields.next() {
+                Some(word) => word.to_owned(),
+                None => continue,
+            };
+            if let Some(frequency) = fields.next() {
+                let frequency: f64 = frequency.parse().map_err(|_| {
+                    let message = format!("line {}: invalid frequency {}", i + 1, frequency);
+                    io::Error::new(io::ErrorKind::InvalidData, message)
+                })?;
+                ret.frequencies.insert(word.clone(), frequency);
+            }
+            if !ret.lookup.insert(word.clone()) { continue; }
+            ret.words.push(word);
+        }
+        return Ok(ret);
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        return self.lookup.contains(word);
+    }
+
+    // How often the word is used, or 0 if the list didn't say.
+    pub fn frequency(&self, word: &str) -> f64 {
+        return self.frequencies.get(word).copied().unwrap_or(0.0);
+    }
+
+    pub fn words(&self) -> Vec<&str> {
+        return self.words.iter().map(String::as_str).collect();
+    }
+
+    // The words with the most used first. Words used equally often, including any the list gave
+    // no frequency for, keep the order they were listed in.
+    pub fn words_by_frequency(&self) -> Vec<&str> {
+        let mut ret = self.words();
+        ret.sort_by(|a, b| self.frequency(b).total_cmp(&self.frequency(a)));
+        return ret;
+    }
+
+    // The words matching the pattern whose other letters can all be made from the rack, using
+    // each letter in it at most as many times as it appears there. Letters the pattern already
+    // fixes are on the board, so don't come from the rack.
+    pub fn anagrams(&self, letters: &[char], pattern: &Constraint) -> Vec<&str> {
+        let mut rack: HashMap<char, usize> = HashMap::new();
+        for &c in letters { *rack.entry(c).or_default() += 1; }
+
+        return self.words.iter()
+            .map(String::as_str)
+            .filter(|word| pattern.matches(word))
+            .filter(|word| {
+                let mut left = rack.clone();
+                return word.chars().enumerate()
+                    .filter(|&(i, _)| pattern.get(i).is_none())
+                    .all(|(_, c)| match left.get_mut(&c) {
+                        Some(count) if *count > 0 => {
+                            *count -= 1;
+                            true
+                        },
+                        _ => false,
+                    });
+            })
+            .collect();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.words.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.words.len() == 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::TempPath;
+
+    fn dictionary(words: &[&str]) -> Dictionary {
+        return Dictionary::new(words.iter().map(|word| word.to_string()));
+    }
+
+    #[test]
+    fn word_lists_are_read_with_their_frequencies() {
+        let file = TempPath::new("dictionary");
+        fs::write(file.path(), "\n  loyal 12.5\nchair\n\nlocus 40\nloyal 3\n").unwrap();
+        let dict = Dictionary::from_file(file.path()).unwrap();
+        assert_eq!(dict.words(), ["loyal", "chair", "locus"]);
+        // A word listed twice keeps its first place, but the last frequency given for it.
+        assert_eq!(dict.frequency("loyal"), 3.0);
+        assert_eq!(dict.frequency("chair"), 0.0);
+        assert_eq!(dict.words_by_frequency(), ["locus", "loyal", "chair"]);
+
+        fs::write(file.path(), "loyal\nchair often\n").unwrap();
+        let err = Dictionary::from_file(file.path()).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: invalid frequency often");
+    }
+
+    #[test]
+    fn anagrams_use_each_letter_of_the_rack_at_most_once() {
+        let dict = dictionary(&["loyal", "royal", "alloy", "local", "lilac", "yucca"]);
+        let rack: Vec<char> = "lyoal".chars().collect();
+        assert_eq!(dict.anagrams(&rack, &Constraint::new()), ["loyal", "alloy"]);
+        // Each word needs a letter the rack doesn't have, or more of one than it has.
+        let rack: Vec<char> = "oyalc".chars().collect();
+        assert_eq!(dict.anagrams(&rack, &Constraint::new()), Vec::<&str>::new());
+        assert_eq!(dict.anagrams(&[], &Constraint::new()), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn anagrams_take_the_fixed_letters_from_the_board() {
+        let dict = dictionary(&["loyal", "royal", "alloy", "local", "lilac", "yucca"]);
+        // The first letter is already there, so only the rest have to come from the rack.
+        let rack: Vec<char> = "oyal".chars().collect();
+        assert_eq!(dict.anagrams(&rack, &Constraint::from("l????")), ["loyal"]);
+        assert_eq!(dict.anagrams(&rack, &Constraint::from("r????")), ["royal"]);
+        let rack: Vec<char> = "ocal".chars().collect();
+        assert_eq!(dict.anagrams(&rack, &Constraint::from("l????")), ["local"]);
+        assert_eq!(dict.anagrams(&rack, &Constraint::from("?????")), Vec::<&str>::new());
+    }
+}
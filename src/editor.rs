@@ -0,0 +1,190 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// The workings of waffle edit, kept apart from the terminal so that they can be driven by any
+// stream of key events. Gaps can't be edited, so the cursor skips straight over them.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::board::{Coord, BoardError, WaffleBoard, WaffleLayout, GAP};
+
+// Written in cells which haven't been filled in yet.
+pub const BLANK: char = '?';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    // Enter: keep the board.
+    Finish,
+    // Escape or Ctrl-C: throw it away.
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub struct Editor {
+    cells: Vec<Vec<char>>,
+    pub cursor: Coord,
+}
+
+impl Editor {
+    // A blank board with the given layout.
+    pub fn new(layout: WaffleLayout) -> Self {
+        let (rows, cols) = layout.size();
+        let cells = (0..rows)
+            .map(|row| (0..cols)
+                .map(|col| if layout.is_gap(Coord{ row: row, col: col }) { GAP } else { BLANK })
+                .collect())
+            .collect();
+        return Self { cells: cells, cursor: Coord{ row: 0, col: 0 } };
+    }
+
+    pub fn from_board(board: &WaffleBoard) -> Self {
+        return Self { cells: board.cells().to_vec(), cursor: Coord{ row: 0, col: 0 } };
+    }
+
+    pub fn get(&self, coord: Coord) -> char {
+        return self.cells[coord.row][coord.col];
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        return (self.cells.len(), self.cells[0].len());
+    }
+
+    pub fn layout(&self) -> WaffleLayout {
+        let (rows, cols) = self.size();
+        return WaffleLayout::new(rows, cols);
+    }
+
+    // The board so far. Blanks are kept, so it only fails if the layout itself is wrong.
+    pub fn board(&self) -> Result<WaffleBoard, BoardError> {
+        return self.text().parse();
+    }
+
+    pub fn text(&self) -> String {
+        return self.cells.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n");
+    }
+
+    // Moves the cursor by the given number of rows and columns, passing over any gaps on the way.
+    // Stays put if there's nowhere to go.
+    fn step(&mut self, rows: isize, cols: isize) {
+        let (height, width) = self.size();
+        let mut cur = self.cursor;
+        loop {
+            let row = cur.row as isize + rows;
+            let col = cur.col as isize + cols;
+            if row < 0 || col < 0 || row as usize >= height || col as usize >= width { return; }
+            cur = Coord{ row: row as usize, col: col as usize };
+            if !self.layout().is_gap(cur) { break; }
+        }
+        self.cursor = cur;
+    }
+
+    pub fn handle(&mut self, event: KeyEvent) -> Action {
+        if event.kind == KeyEventKind::Release { return Action::Continue; }
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+
+        match event.code {
+            KeyCode::Char('c') if ctrl => return Action::Cancel,
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Enter => return Action::Finish,
+            KeyCode::Left => self.step(0, -1),
+            KeyCode::Right => self.step(0, 1),
+            KeyCode::Up => self.step(-1, 0),
+            KeyCode::Down => self.step(1, 0),
+            // Clears the cell, or the one before it if it's already clear.
+            KeyCode::Backspace => {
+                if self.get(self.cursor) == BLANK { self.step(0, -1); }
+                self.cells[self.cursor.row][self.cursor.col] = BLANK;
+            },
+            KeyCode::Delete => self.cells[self.cursor.row][self.cursor.col] = BLANK,
+            // Typing fills in the cell and moves on, as in a crossword.
+            KeyCode::Char(c) if c.is_alphabetic() && !ctrl => {
+                self.cells[self.cursor.row][self.cursor.col] = c;
+                self.step(0, 1);
+            },
+            _ => (),
+        }
+        return Action::Continue;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::loyal;
+
+    fn press(editor: &mut Editor, code: KeyCode) -> Action {
+        return editor.handle(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn type_text(editor: &mut Editor, text: &str) {
+        text.chars().for_each(|c| { press(editor, KeyCode::Char(c)); });
+    }
+
+    #[test]
+    fn typing_fills_in_the_board_and_skips_the_gaps() {
+        let mut editor = Editor::new(WaffleLayout::standard_5x5());
+        assert_eq!(editor.text(), "?????\n? ? ?\n?????\n? ? ?\n?????");
+        for (row, text) in ["loyal", "iuo", "locus", "ace", "chair"].iter().enumerate() {
+            editor.cursor = Coord{ row: row, col: 0 };
+            type_text(&mut editor, text);
+        }
+        assert_eq!(editor.board(), Ok(loyal()));
+
+        // Moving right from the first letter of the second row jumps the gap between them.
+        editor.cursor = Coord{ row: 1, col: 0 };
+        press(&mut editor, KeyCode::Right);
+        assert_eq!(editor.cursor, Coord{ row: 1, col: 2 });
+        press(&mut editor, KeyCode::Down);
+        press(&mut editor, KeyCode::Down);
+        assert_eq!(editor.cursor, Coord{ row: 3, col: 2 });
+        // Past the last row there's nowhere to go, so the cursor stays put.
+        press(&mut editor, KeyCode::Down);
+        press(&mut editor, KeyCode::Down);
+        assert_eq!(editor.cursor, Coord{ row: 4, col: 2 });
+    }
+
+    #[test]
+    fn backspace_and_delete_clear_cells() {
+        let mut editor = Editor::from_board(&loyal());
+        editor.cursor = Coord{ row: 0, col: 4 };
+        press(&mut editor, KeyCode::Delete);
+        assert_eq!(editor.get(Coord{ row: 0, col: 4 }), BLANK);
+        // The cell is already clear, so backspace clears the one before it and moves there.
+        press(&mut editor, KeyCode::Backspace);
+        assert_eq!(editor.cursor, Coord{ row: 0, col: 3 });
+        assert_eq!(editor.text().lines().next(), Some("loy??"));
+        type_text(&mut editor, "al");
+        assert_eq!(editor.board(), Ok(loyal()));
+    }
+
+    #[test]
+    fn enter_finishes_and_escape_or_ctrl_c_cancels() {
+        let mut editor = Editor::from_board(&loyal());
+        assert_eq!(press(&mut editor, KeyCode::Enter), Action::Finish);
+        assert_eq!(press(&mut editor, KeyCode::Esc), Action::Cancel);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(editor.handle(ctrl_c), Action::Cancel);
+
+        // Neither releasing a key nor typing something which isn't a letter changes anything.
+        let release = KeyEvent::new_with_kind(KeyCode::Char('z'), KeyModifiers::NONE,
+                                              KeyEventKind::Release);
+        assert_eq!(editor.handle(release), Action::Continue);
+        assert_eq!(press(&mut editor, KeyCode::Char('1')), Action::Continue);
+        assert_eq!(editor.board(), Ok(loyal()));
+        assert_eq!(editor.cursor, Coord{ row: 0, col: 0 });
+    }
+}
@@ -11,61 +11,199 @@
 // You should have received a copy of the GNU General Public License along with WaffleSolver. If
 // not, see <https://www.gnu.org/licenses/>.
 
-mod constraints;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::{env, fs, process};
 
-use std::path::Path;
-use std::fs;
-use constraints::ConstraintBoard;
+use waffler::answers::{Solver, SolverOptions, Stop};
+use waffler::constraints::ConstraintBoard;
+use waffler::dictionary::Dictionary;
 
-type Coord = (usize, usize);
+// Prints each solution, with the separator on a line of its own between each one and the next.
+struct Printer<'a> {
+    separator: &'a str,
+    shown: usize,
+}
 
-fn try_word(board: ConstraintBoard, word: &str, indices: &[Coord]) -> Option<ConstraintBoard> {
-    assert!(word.len() == indices.len(), "Word and indices are different lengths!");
-    if word.len() == 0 { return Some(board); }
-    let c: char = word.chars().next().unwrap();
-    let (row, col) = indices[0];
+impl<'a> Printer<'a> {
+    fn new(separator: &'a str) -> Self {
+        return Self { separator: separator, shown: 0 };
+    }
 
-    return match board.with(row, col, c) {
-        Some(next) => try_word(next, &word[1..], &indices[1..]),
-        None => None,
-    };
+    fn show_solution(&mut self, board: &ConstraintBoard) {
+        if self.shown > 0 { println!("{}", self.separator); }
+        println!("{}", board);
+        self.shown += 1;
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    // How many threads to search with. 1 searches on the main thread alone.
+    jobs: usize,
+    // Stop after the first solution.
+    first: bool,
+    solver: SolverOptions,
+    // Only fill in the board with these letters, rather than the ones the board file holds.
+    letters: Option<String>,
+    // Printed between solutions. Empty by default, which leaves a blank line.
+    separator: String,
+    // Print difficulty_estimate for the board instead of solving it.
+    difficulty: bool,
+    // Treat the tiles as opaque symbols, with the known cells marked by a 'g' in this file.
+    symbols: Option<PathBuf>,
 }
 
-fn find_solutions(board: ConstraintBoard, wordlist: &[&str]) {
-    let words = board.get_all_words();
-    if words.len() == 0 {
-        println!("{}", board.to_string());
-        println!();
-        return;
+impl Default for Options {
+    fn default() -> Self {
+        return Self { jobs: 1, first: false, solver: SolverOptions::default(), letters: None,
+                     separator: String::new(), difficulty: false, symbols: None };
     }
+}
 
-    let (constraint, indices) = words.into_iter().next().unwrap();
-    let possible_words = wordlist.iter()
-        .filter(|word| constraint.matches(word))
-        .collect::<Vec<_>>();
+fn parse_args() -> (Options, PathBuf, PathBuf) {
+    let mut options = Options::default();
+    let mut paths: Vec<PathBuf> = Vec::new();
 
-    for possible_word in possible_words {
-        let next = match try_word(board.clone(), &possible_word, &indices) {
-            None => continue,
-            Some(next) => next,
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| -> String {
+            return args.next().unwrap_or_else(|| {
+                eprintln!("Expected a value after {}", flag);
+                process::exit(1);
+            });
         };
-        find_solutions(next, wordlist);
+
+        match arg.as_str() {
+            "--first" => options.first = true,
+            "--max-branch-truncate" => options.solver.truncate = true,
+            "--dump-budget" => options.solver.dump_budget = true,
+            "--difficulty" => options.difficulty = true,
+            "--max-depth" => {
+                let max_depth = value(&arg);
+                options.solver.max_depth = match max_depth.parse() {
+                    Ok(max_depth) => Some(max_depth),
+                    _ => {
+                        eprintln!("Invalid maximum depth: {}", max_depth);
+                        process::exit(1);
+                    },
+                };
+            },
+            "--letters" => options.letters = Some(value(&arg)),
+            "--separator" => options.separator = value(&arg),
+            "--symbols" => options.symbols = Some(PathBuf::from(value(&arg))),
+            "--max-branch" => {
+                let max_branch = value(&arg);
+                options.solver.max_branch = match max_branch.parse() {
+                    Ok(max_branch) if max_branch > 0 => Some(max_branch),
+                    _ => {
+                        eprintln!("Invalid maximum branching factor: {}", max_branch);
+                        process::exit(1);
+                    },
+                };
+            },
+            "--jobs" => {
+                let jobs = value(&arg);
+                options.jobs = match jobs.parse() {
+                    Ok(jobs) if jobs > 0 => jobs,
+                    _ => {
+                        eprintln!("Invalid number of jobs: {}", jobs);
+                        process::exit(1);
+                    },
+                };
+            },
+            flag if flag.starts_with("--") => {
+                eprintln!("Unknown flag: {}", flag);
+                process::exit(1);
+            },
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    if paths.len() != 2 {
+        eprintln!("Expected 2 command line arguments but got {}", paths.len());
+        process::exit(1);
     }
+
+    let board = paths.pop().unwrap();
+    let wordlist = paths.pop().unwrap();
+    return (options, wordlist, board);
 }
 
 fn main() -> Result<(), std::io::Error> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() != 3 {
-        eprintln!("Expected 2 command line arguments but got {}", args.len() - 1);
-        std::process::exit(1);
+    let (options, wordlist_path, board_path) = parse_args();
+
+    let dict = Dictionary::from_file(&wordlist_path)?;
+    if dict.is_empty() {
+        eprintln!("Error: word list is empty after filtering");
+        process::exit(1);
     }
 
-    let wordlist: Vec<String> = fs::read_to_string(&Path::new(&args[1]))?
-        .lines()
-        .map(str::trim)
-        .map(String::from)
-        .collect();
-    let source = ConstraintBoard::from_file(Path::new(&args[2])).expect("Failed to parse board");
-    find_solutions(source, &wordlist.iter().map(String::as_str).collect::<Vec<&str>>());
+    let read = |path: &PathBuf| -> String {
+        return fs::read_to_string(path).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path.display(), err);
+            process::exit(1);
+        });
+    };
+    let parsed = match &options.symbols {
+        Some(known_path) => ConstraintBoard::from_symbols(&read(&board_path), &read(known_path)),
+        None => read(&board_path).parse(),
+    };
+    let mut source = parsed.unwrap_or_else(|err| {
+        eprintln!("{}: {}", board_path.display(), err);
+        process::exit(1);
+    });
+    if let Some(letters) = &options.letters {
+        source = source.with_rack(letters).unwrap_or_else(|err| {
+            eprintln!("--letters: {}", err);
+            process::exit(1);
+        });
+    }
+    let word_len = source.word_len();
+    if !dict.words().iter().any(|word| word.chars().count() == word_len) {
+        eprintln!("Warning: word list has no words of length {}", word_len);
+    }
+    if options.difficulty {
+        println!("{:.2}", source.difficulty_estimate(&dict));
+        return Ok(());
+    }
+
+    // When only some of each word's candidates are tried, they should be the likeliest ones.
+    let wordlist = if options.solver.truncate { dict.words_by_frequency() } else { dict.words() };
+
+    let solver = Solver::new(&wordlist, options.solver);
+    let mut printer = Printer::new(&options.separator);
+    let result = if options.jobs > 1 {
+        solver.find_solutions_parallel(source, options.jobs, options.first)
+            .map(|solutions| solutions.iter().for_each(|solution| printer.show_solution(solution)))
+    } else {
+        let result = solver.find_solutions(source, &mut |solution| {
+            printer.show_solution(solution);
+            return if options.first { ControlFlow::Break(Stop::Done) } else { ControlFlow::Continue(()) };
+        });
+        match result {
+            ControlFlow::Break(Stop::Done) | ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(stop) => Err(stop),
+        }
+    };
+
+    match result {
+        Ok(()) | Err(Stop::Done) => (),
+        Err(Stop::TooManyCandidates { count }) => {
+            eprintln!("Error: a word has {} candidates, more than --max-branch allows", count);
+            process::exit(1);
+        },
+        Err(Stop::TooDeep { depth }) => {
+            eprintln!("Error: the search went {} words deep, more than --max-depth allows", depth);
+            process::exit(1);
+        },
+        Err(Stop::NoProgress) => {
+            eprintln!("Error: filling in a word left no fewer words to fill; giving up");
+            process::exit(1);
+        },
+    }
+    if solver.truncated() {
+        eprintln!("Warning: some words had too many candidates and were truncated; solutions may be missing");
+    }
     return Ok(());
 }
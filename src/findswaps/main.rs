@@ -11,230 +11,649 @@
 // You should have received a copy of the GNU General Public License along with WaffleSolver. If
 // not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::{BTreeSet, HashMap, HashSet};
-use std::path::Path;
-use std::{cmp, env, fmt, fs, io, process};
-
-use itertools::Itertools;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Coord {
-    row: usize,
-    col: usize,
+use std::path::{Path, PathBuf};
+#[cfg(feature = "ctrlc")]
+use std::sync::atomic::Ordering;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::{env, process};
+
+use waffler::board::{Coord, CoordStyle, Swap, SwapMode, WaffleBoard};
+use waffler::csv;
+use waffler::dictionary::Dictionary;
+use waffler::profile::Profile;
+use waffler::heuristic::CycleBound;
+use waffler::search::{
+    can_reach, diagnose, find_all_swaps, find_min_swaps, min_swaps, order_words_first,
+    solve_interruptible, SearchOptions, SearchState, SolveOutcome, MAX_SWAPS,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    // One swap per line, as r1,c1-r2,c2.
+    Swaps,
+    // A header, then one row per swap with the letters and where they are.
+    Csv,
 }
 
-impl fmt::Display for Coord {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        return write!(f, "({},{})", self.row, self.col);
+impl OutputFormat {
+    fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            "swaps" => Some(Self::Swaps),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        };
     }
 }
 
-#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
-struct Swap {
-    a: Coord,
-    b: Coord,
+// Which of the optimal solutions to show, when there's more than one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Solutions {
+    #[default]
+    First,
+    All,
+    // Just how many there are.
+    Count,
 }
 
-impl Swap {
-    fn new(a: Coord, b: Coord) -> Self {
-        let first = cmp::min(a, b);
-        let second = cmp::max(a, b);
-        return Self { a: first, b: second };
+impl Solutions {
+    fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "first" => Some(Self::First),
+            "all" => Some(Self::All),
+            "count" => Some(Self::Count),
+            _ => None,
+        };
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
-struct WaffleBoard {
-    cells: Vec<Vec<char>>
+#[derive(Debug, Default)]
+struct Options {
+    // Annotate each swap with the words it completes.
+    explain: bool,
+    // With explain, also point out rows and columns a swap turns into some other word in this
+    // list, which look finished but aren't the answer.
+    explain_words: Option<Dictionary>,
+    // After each board, list the words which have just become complete.
+    word_progress: bool,
+    // How coordinates are written in swap instructions.
+    coord_style: CoordStyle,
+    // Report statistics about the puzzle instead of a solution.
+    stats: bool,
+    // Show the solution backwards, from the target to the scramble.
+    reverse: bool,
+    // Add how many same-letter swaps the search skipped to the statistics.
+    include_trivial: bool,
+    // Check the search's answer against find_min_swaps. Not documented, as it's for development.
+    cross_check: bool,
+    // Show only the solved board and the number of swaps, rather than every step.
+    final_only: bool,
+    // Show only the number of swaps and the swaps themselves, labelled as a plan.
+    dry_run: bool,
+    // Reorder the swaps to complete words as soon as possible.
+    words_first: bool,
+    // Report where the search spent its time.
+    profile: bool,
+    search: SearchOptions,
+    // Treat upper and lowercase letters as the same.
+    ignore_case: bool,
+    format: OutputFormat,
+    // When there's no solution, explain why rather than just saying so.
+    explain_unsolvable: bool,
+    // Printed on a line of its own between the boards of a transformation. By default the swap
+    // instructions are all that separate them.
+    separator: Option<String>,
+    solutions: Solutions,
+    // The target given by its words, eg "row0=loyal, col0=llama", rather than as a board.
+    target_words: Option<String>,
+    // A word list to check the target against before solving, to catch mistakes in typing it up.
+    verify_target: Option<PathBuf>,
+    // Where to save the search as it goes, and to pick it up from if it's already there.
+    #[cfg(feature = "serde")]
+    checkpoint: Option<PathBuf>,
 }
 
-impl Clone for WaffleBoard {
-    fn clone(&self) -> Self {
-        return Self {
-            cells: self.cells.iter().cloned().collect(),
+// Returns the options along with the paths to the boards, or an empty list if the boards weren't
+// given on the command line. There's no path for the target when it's given by --target-words.
+fn parse_args() -> (Options, Vec<PathBuf>) {
+    let mut options = Options::default();
+    let mut paths: Vec<PathBuf> = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| -> String {
+            return args.next().unwrap_or_else(|| {
+                eprintln!("Expected a value after {}", flag);
+                process::exit(1);
+            });
         };
-    }
-}
 
-impl WaffleBoard {
-    fn new(path: &Path) -> io::Result<Self> {
-        let cells: Vec<String> = fs::read_to_string(path)?
-            .lines()
-            .map(|line| line.to_owned())
-            .collect();
-
-        assert!(cells.len() > 0, "Expected at least one line!");
-        let len = cells[0].len();
-        assert!(cells.iter().all(|line| line.len() == len),
-                "Expected all lines to be the same length!");
-
-        return Ok(Self {
-            cells: cells.into_iter()
-                        .map(|line| line.chars().collect())
-                        .collect(),
-        });
-    }
-
-    fn swap(&self, swap: Swap) -> Self {
-        let Swap { a, b } = swap;
-        let mut c = self.cells.clone();
-        (c[a.row][a.col], c[b.row][b.col]) = (c[b.row][b.col], c[a.row][a.col]);
-        return Self { cells: c };
+        match arg.as_str() {
+            "--explain" => options.explain = true,
+            "--explain-words" => {
+                let path = PathBuf::from(value(&arg));
+                let dict = Dictionary::from_file(&path).unwrap_or_else(|err| {
+                    eprintln!("{}: {}", path.display(), err);
+                    process::exit(1);
+                });
+                options.explain = true;
+                options.explain_words = Some(dict);
+            },
+            "--word-progress" => options.word_progress = true,
+            "--coord-style" => {
+                let style = value(&arg);
+                options.coord_style = CoordStyle::parse(&style).unwrap_or_else(|| {
+                    eprintln!("Unknown coordinate style: {} (expected zero, one, or a1)", style);
+                    process::exit(1);
+                });
+            },
+            "--stats" => options.stats = true,
+            "--include-trivial" => options.include_trivial = true,
+            "--reverse" => options.reverse = true,
+            "--cross-check" => options.cross_check = true,
+            "--final-only" => options.final_only = true,
+            "--dry-run" => options.dry_run = true,
+            "--prefer" => {
+                let prefer = value(&arg);
+                options.words_first = match prefer.as_str() {
+                    "words-first" => true,
+                    "any" => false,
+                    _ => {
+                        eprintln!("Unknown preference: {} (expected words-first or any)", prefer);
+                        process::exit(1);
+                    },
+                };
+            },
+            "--profile" => options.profile = true,
+            "--canonicalize" => options.search.canonicalize = true,
+            "--exact-keys" => options.search.exact_keys = true,
+            "--ignore-case" => options.ignore_case = true,
+            "--explain-unsolvable" => options.explain_unsolvable = true,
+            "--separator" => options.separator = Some(value(&arg)),
+            "--target-words" => options.target_words = Some(value(&arg)),
+            "--solutions" => {
+                let solutions = value(&arg);
+                options.solutions = Solutions::parse(&solutions).unwrap_or_else(|| {
+                    eprintln!("Unknown choice of solutions: {} (expected first, all, or count)",
+                              solutions);
+                    process::exit(1);
+                });
+            },
+            "--verify-target" => options.verify_target = Some(PathBuf::from(value(&arg))),
+            #[cfg(feature = "serde")]
+            "--checkpoint" => options.checkpoint = Some(PathBuf::from(value(&arg))),
+            #[cfg(not(feature = "serde"))]
+            "--checkpoint" => {
+                eprintln!("--checkpoint needs findswaps to be built with the serde feature");
+                process::exit(1);
+            },
+            "--swap-mode" => {
+                let mode = value(&arg);
+                options.search.swap_mode = SwapMode::parse(&mode).unwrap_or_else(|| {
+                    eprintln!("Unknown swap mode: {} (expected any, row, col, or line)", mode);
+                    process::exit(1);
+                });
+            },
+            "--format" => {
+                let format = value(&arg);
+                options.format = OutputFormat::parse(&format).unwrap_or_else(|| {
+                    eprintln!("Unknown format: {} (expected text, json, swaps, or csv)", format);
+                    process::exit(1);
+                });
+            },
+            flag if flag.starts_with("--") => {
+                eprintln!("Unknown flag: {}", flag);
+                process::exit(1);
+            },
+            _ => paths.push(PathBuf::from(arg)),
+        }
     }
 
-    fn size(&self) -> (usize, usize) {
-        return (self.cells.len(), self.cells[0].len());
+    let expected = if options.target_words.is_some() { 1 } else { 2 };
+    if paths.len() != 0 && paths.len() != expected {
+        eprintln!("Expected {} board arguments but got {}", expected, paths.len());
+        process::exit(1);
     }
+    return (options, paths);
+}
 
-    fn get(&self, coord: Coord) -> char {
-        return self.cells[coord.row][coord.col];
-    }
+// Describes which words become complete (every cell matching the target) as a result of the swap,
+// which were not complete beforehand. Given a word list, also describes those which the swap
+// turns into a word from it other than the answer.
+fn explain_swap(cur: &WaffleBoard, into: &WaffleBoard, step: Swap,
+                dict: Option<&Dictionary>) -> Vec<String> {
+    let next = cur.swap(step);
+    let complete = |board: &WaffleBoard, cells: &[Coord]| {
+        cells.iter().all(|&coord| board.get(coord) == into.get(coord))
+    };
+    let spelled = |board: &WaffleBoard, cells: &[Coord]| -> String {
+        return cells.iter().map(|&coord| board.get(coord).to_ascii_lowercase()).collect();
+    };
+    let is_word = |word: &str| dict.is_some_and(|dict| dict.contains(word));
+
+    return into.words().into_iter()
+        .filter(|(_, cells)| cells.contains(&step.a) || cells.contains(&step.b))
+        .filter_map(|(name, cells)| {
+            if complete(&next, &cells) && !complete(cur, &cells) {
+                let word = spelled(into, &cells);
+                return Some(format!("completes {} ({})", word.to_uppercase(), name));
+            }
+            let word = spelled(&next, &cells);
+            if complete(&next, &cells) || !is_word(&word) || is_word(&spelled(cur, &cells)) {
+                return None;
+            }
+            return Some(format!("spells {} ({}), which is a word but not the answer",
+                                word.to_uppercase(), name));
+        })
+        .collect();
+}
 
-    fn diff(&self, other: &Self) -> Vec<Coord> {
-        let (selfsize, othersize) = (self.size(), other.size());
-        let fmt = |size: (usize, usize)| format!("{}x{}", size.0, size.1);
-        assert!(self.size() == other.size(),
-                "Size mismatch: {} vs {}", fmt(selfsize), fmt(othersize));
+// Shows each board in turn, with the swap that leads to the next between them. With word_progress,
+// each board is followed by the words which have become complete since the one before it.
+fn show_transformation(from: &WaffleBoard, into: &WaffleBoard, steps: &[Swap], options: &Options) {
+    println!("{}", transformation(from, into, steps, options).join("\n"));
+}
 
-        let mut ret = Vec::new();
-        for row in 0..selfsize.0 {
-            for col in 0..selfsize.1 {
-                let coord = Coord{ row: row, col: col };
-                let selfcell = self.get(coord);
-                let othercell = other.get(coord);
-                if selfcell == othercell { continue; }
-                ret.push(coord);
+// The lines show_transformation prints. These are built up in a loop over one board rather than by
+// recursing, which long solutions could overflow the stack with.
+fn transformation(from: &WaffleBoard, into: &WaffleBoard, steps: &[Swap],
+                  options: &Options) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut cur = from.clone();
+    let mut complete = if options.word_progress { cur.complete_words(into) } else { Vec::new() };
+    lines.push(cur.display());
+    // Words which start out right are listed too, so that by the end every word has been.
+    if complete.len() > 0 { lines.push(format!("complete: {}", complete.join(", "))); }
+    for &step in steps {
+        lines.push(format!("- {}", cur.describe_swap(step, options.coord_style)));
+        if options.explain {
+            for explanation in explain_swap(&cur, into, step, options.explain_words.as_ref()) {
+                lines.push(format!("  {}", explanation));
             }
         }
-        return ret;
+        if let Some(separator) = &options.separator { lines.push(separator.clone()); }
+
+        cur.swap_mut(step);
+        lines.push(cur.display());
+        if options.word_progress {
+            let now = cur.complete_words(into);
+            let new: Vec<&str> = now.iter()
+                .filter(|name| !complete.contains(name))
+                .map(String::as_str)
+                .collect();
+            if new.len() > 0 { lines.push(format!("complete: {}", new.join(", "))); }
+            complete = now;
+        }
     }
+    return lines;
+}
 
-    fn score(&self, other: &Self) -> usize {
-        // Score is just the number of different cells between itself and the target.
-        return self.diff(other).len();
-    }
+fn show_final(from: &WaffleBoard, steps: &[Swap]) {
+    println!("{}", from.apply_all(steps).display());
+    println!("{} swaps", steps.len());
+}
 
-    fn display(&self) -> String {
-        return self.cells.iter()
-            .map(|row| row.iter().collect::<String>())
-            .join("\n");
-    }
+fn show_plan(steps: &[Swap]) {
+    println!("plan: {} swaps (not applied)", steps.len());
+    steps.iter().for_each(|step| println!("{}", step));
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
-struct State<'a> {
-    cur: WaffleBoard,
-    dest: &'a WaffleBoard,
+// A tile as a JSON string. Boards of symbols can hold quotes and backslashes, which need escaping.
+fn json_letter(c: char) -> String {
+    return match c {
+        '"' | '\\' => format!("\"\\{}\"", c),
+        c if c.is_control() => format!("\"\\u{:04x}\"", c as u32),
+        c => format!("\"{}\"", c),
+    };
 }
 
-impl<'a> State<'a> {
-    fn new(cur: WaffleBoard, dest: &'a WaffleBoard) -> Self {
-        return Self {
-            cur: cur,
-            dest: dest,
-        };
+fn swap_json(cur: &WaffleBoard, step: Swap) -> String {
+    let cell = |coord: Coord| {
+        format!("{{\"row\":{},\"col\":{},\"letter\":{}}}",
+                coord.row, coord.col, json_letter(cur.get(coord)))
+    };
+    return format!("{{\"a\":{},\"b\":{}}}", cell(step.a), cell(step.b));
+}
+
+fn show_json(from: &WaffleBoard, steps: &[Swap]) {
+    let mut cur = from.clone();
+    let mut swaps = Vec::new();
+    for &step in steps {
+        swaps.push(swap_json(&cur, step));
+        cur = cur.swap(step);
     }
+    println!("{{\"swaps\":[{}]}}", swaps.join(","));
 }
 
-impl<'a> Ord for State<'a> {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        let ret = self.cur.score(self.dest).cmp(&other.cur.score(other.dest));
-        return match ret {
-            cmp::Ordering::Equal => self.cur.cells.cmp(&other.cur.cells),
-            _ => ret,
-        };
+fn show_csv(from: &WaffleBoard, steps: &[Swap]) {
+    println!("step,letter_a,row_a,col_a,letter_b,row_b,col_b");
+    let mut cur = from.clone();
+    for (i, &step) in steps.iter().enumerate() {
+        let (a, b) = (step.a, step.b);
+        let letter = |coord: Coord| csv::field(&cur.get(coord).to_string());
+        println!("{},{},{},{},{},{},{}",
+                 i + 1, letter(a), a.row, a.col, letter(b), b.row, b.col);
+        cur = cur.swap(step);
     }
 }
 
-impl<'a> PartialOrd for State<'a> {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        return Some(self.cmp(other));
+// Shows a solution in whichever format was asked for, in the order asked for.
+fn show_path(from: &WaffleBoard, into: &WaffleBoard, mut path: Vec<Swap>, options: &Options) {
+    if options.words_first {
+        path = order_words_first(&path, from, into).expect("The solution's own order should work");
     }
+    // Undoing a swap is making it again, so the same swaps in reverse lead back to the scramble.
+    let (from, into) = match options.reverse {
+        true => {
+            path.reverse();
+            assert!(&into.apply_all(&path) == from, "The reversed swaps should undo the solution");
+            (into, from)
+        },
+        false => (from, into),
+    };
+    show_steps(from, into, &path, options);
 }
 
-fn find_swaps(from: &WaffleBoard, into: &WaffleBoard) -> Option<Vec<Swap>> {
-    let get_swaps = |board: &WaffleBoard| -> Vec<Swap> {
-        let differences = board.diff(into);
-        if differences.len() == 0 { return Vec::new(); }
-        assert!(differences.len() > 1, "Expected at least 2 differences; nothing to swap!");
-        // Find all unique possible swaps; note that Swap::new already acts as a sorted pair of
-        // coordintes, so any two swaps of point a and point b are identical.
-        let uniques: HashSet<Swap> = differences.into_iter()
-            .combinations(2)
-            .map(|pair| Swap::new(pair[0], pair[1]))
-            .collect();
-        // sort the possible swaps, so that the output will be deterministic.
-        let mut sorted: Vec<Swap> = uniques.into_iter().collect();
-        sorted.sort();
-        return sorted;
+// Shows the given swaps in whichever format was asked for, without reordering them.
+fn show_steps(from: &WaffleBoard, into: &WaffleBoard, path: &[Swap], options: &Options) {
+    match options.format {
+        OutputFormat::Text if options.dry_run => show_plan(path),
+        OutputFormat::Text if options.final_only => show_final(from, path),
+        OutputFormat::Text => show_transformation(from, into, path, options),
+        OutputFormat::Json => show_json(from, path),
+        OutputFormat::Csv => show_csv(from, path),
+        OutputFormat::Swaps => path.iter().for_each(|step| println!("{}", step)),
     };
+}
 
-    let mut map: HashMap<WaffleBoard, Vec<Swap>> = HashMap::new();
-    let mut states: BTreeSet<State> = BTreeSet::new();
+fn show_stats(from: &WaffleBoard, into: &WaffleBoard, options: &Options) {
+    let min = min_swaps(from, into, options.search.swap_mode);
+    // Under the restricted modes, min_swaps gives up on paths longer than MAX_SWAPS.
+    let reachable = can_reach(from, into, options.search.swap_mode);
+    let solutions = find_all_swaps(from, into, options.search.swap_mode).len();
+    let solved = from.solved_percent(into);
+    // The full search solve makes, without the shortcuts it can sometimes take.
+    let mut search = SearchState::with_heuristic(from, into, &options.search, CycleBound);
+    let _ = search.run(&mut Profile::new(false), &mut |_| ());
+    let work = search.stats();
+    let trivial = work.trivial_by_step.iter().map(usize::to_string).collect::<Vec<_>>();
+
+    match options.format {
+        OutputFormat::Json => {
+            let min = min.map_or("null".to_owned(), |min| min.to_string());
+            let trivial = match options.include_trivial {
+                true => format!(",\"trivial_swaps_by_step\":[{}]", trivial.join(",")),
+                false => String::new(),
+            };
+            println!("{{\"reachable\":{},\"min_swaps\":{},\"optimal_solutions\":{},\"solved_percent\":{:.1},\
+                      \"explored\":{},\"peak_frontier\":{},\"peak_visited\":{}{}}}",
+                     reachable, min, solutions, solved,
+                     work.explored, work.peak_frontier, work.peak_visited, trivial);
+        },
+        OutputFormat::Text | OutputFormat::Swaps | OutputFormat::Csv => {
+            println!("reachable: {}", if reachable { "yes" } else { "no" });
+            match min {
+                Some(min) => println!("minimum swaps: {}", min),
+                None if reachable => println!("minimum swaps: more than {}", MAX_SWAPS),
+                None => (),
+            }
+            println!("optimal solutions: {}", solutions);
+            println!("solved at start: {:.1}%", solved);
+            println!("boards explored: {}", work.explored);
+            println!("peak boards waiting: {}", work.peak_frontier);
+            println!("peak boards seen: {}", work.peak_visited);
+            if options.include_trivial {
+                println!("same-letter swaps skipped by step: {}", trivial.join(" "));
+            }
+        },
+    }
+}
 
-    map.insert(from.clone(), Vec::new());
-    states.insert(State::new(from.clone(), into));
+// Checks the search's answer against find_min_swaps, which works the distances out another way.
+// They have to agree on how many swaps are needed, and each one's swaps have to reach the target.
+fn cross_check(from: &WaffleBoard, into: &WaffleBoard, outcome: &SolveOutcome,
+               mode: SwapMode) -> Result<(), String> {
+    let found = match outcome {
+        SolveOutcome::Solved(steps) => Some(steps),
+        // There's nothing to compare a search that didn't finish with.
+        SolveOutcome::Interrupted(_) => return Ok(()),
+        _ => None,
+    };
+    let other = find_min_swaps(from, into, mode);
 
-    // BTreeSet is a sorted set. pop_first  will return the lowest-scored item. That means, we will
-    // continually find the (or a) board with the fewest differences between itself and the target.
-    while let Some(State { cur, dest: _ }) = states.pop_first() {
-        // A reference to the old path, so we can check its length.
-        let prev_path = map.get(&cur).unwrap();
-        if prev_path.len() > 10 { continue; }
+    for (name, steps) in [("the search", found), ("find_min_swaps", other.as_ref())] {
+        if steps.is_some_and(|steps| from.try_apply_all(steps).ok().as_ref() != Some(into)) {
+            return Err(format!("the swaps from {} don't reach the target", name));
+        }
+    }
+    let (found, other) = (found.map(Vec::len), other.map(|steps| steps.len()));
+    if found != other {
+        return Err(format!("the search found {:?} swaps but find_min_swaps found {:?}", found, other));
+    }
+    return Ok(());
+}
 
-        // If it's not already too long, we can take the time to copy it into the local scope.
-        let steps: Vec<Swap> = prev_path.iter().copied().collect();
-        let cur_score = cur.score(into);
-        if cur_score == 0 { return Some(steps); }
+fn load_board(path: &Path) -> WaffleBoard {
+    return WaffleBoard::new(path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path.display(), err);
+        process::exit(1);
+    });
+}
+
+// Reads a board from an environment variable, for when files are awkward to provide. The rows may
+// be separated by newlines or by a literal \n. Any other character, '/' included, is a tile.
+fn load_board_from_env(var: &str) -> WaffleBoard {
+    let value = env::var(var).unwrap_or_else(|_| {
+        eprintln!("Expected board files as arguments, or boards in WAFFLE_FROM and WAFFLE_TO");
+        process::exit(1);
+    });
+    let text = value.replace("\\n", "\n");
+    return text.parse().unwrap_or_else(|err| {
+        eprintln!("{}: {}", var, err);
+        process::exit(1);
+    });
+}
 
-        for swap in get_swaps(&cur) {
-            let next = cur.swap(swap);
+// How many boards to explore between saving checkpoints.
+#[cfg(feature = "serde")]
+const CHECKPOINT_INTERVAL: usize = 100000;
+
+// The same as solve, but saving the search to path every so often, and resuming it from there if
+// it's already been started. The checkpoint is removed once the search is over, unless it was
+// interrupted, in which case it's saved as it was left so it can be carried on with later.
+#[cfg(feature = "serde")]
+fn solve_checkpointed(from: &WaffleBoard, into: &WaffleBoard, options: &Options, path: &Path,
+                      interrupt: Arc<AtomicBool>, profile: &mut Profile) -> SolveOutcome {
+    if from.letter_counts() != into.letter_counts() { return SolveOutcome::LettersDiffer; }
+    // Like solve, skip the search, and so the checkpoint, when the greedy chain can't be beaten.
+    if let Some(steps) = waffler::search::greedy_shortcut(from, into, options.search.swap_mode) {
+        let _ = std::fs::remove_file(path);
+        return SolveOutcome::Solved(steps);
+    }
 
-            // If this swap does not improve our position, skip it.
-            if next.score(into) >= cur_score { continue; }
+    let mut state = match path.exists() {
+        false => SearchState::with_heuristic(from, into, &options.search, CycleBound),
+        true => SearchState::load(path, CycleBound).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path.display(), err);
+            process::exit(1);
+        }),
+    };
+    if !state.is_for(from, into, &options.search) {
+        eprintln!("{}: checkpoint is for a different puzzle or different options", path.display());
+        process::exit(1);
+    }
+    state.interrupt_on(interrupt);
+
+    let mut explored: usize = 0;
+    let result = state.run(profile, &mut |state| {
+        explored += 1;
+        if !explored.is_multiple_of(CHECKPOINT_INTERVAL) { return; }
+        if let Err(err) = state.save(path) { eprintln!("{}: {}", path.display(), err); }
+    });
+    let outcome = SolveOutcome::from_search(result);
+    if let SolveOutcome::Interrupted(_) = outcome {
+        if let Err(err) = state.save(path) { eprintln!("{}: {}", path.display(), err); }
+    } else {
+        let _ = std::fs::remove_file(path);
+    }
+    return outcome;
+}
 
-            let prev_len = map.get(&next).map(Vec::len);
+fn main() {
+    let (options, paths) = parse_args();
 
-            // If we've already seen this state before, and the old path is no shorter than the
-            // current path (ie, we have no improvement), then continue.
-            if prev_len.is_some() && prev_len.unwrap() <= steps.len() + 1 { continue; }
+    // Boards given on the command line take precedence over the environment.
+    let mut from_board = match paths.first() {
+        Some(from) => load_board(from),
+        None => load_board_from_env("WAFFLE_FROM"),
+    };
+    let mut into_board = match (&options.target_words, paths.get(1)) {
+        (Some(words), _) => WaffleBoard::from_words(words).unwrap_or_else(|err| {
+            eprintln!("--target-words: {}", err);
+            process::exit(1);
+        }),
+        (None, Some(into)) => load_board(into),
+        (None, None) => load_board_from_env("WAFFLE_TO"),
+    };
+    if let Err(err) = from_board.check_same_layout(&into_board) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+    if options.ignore_case {
+        from_board = from_board.normalized();
+        into_board = into_board.normalized();
+    }
 
-            // Otherwise we have a new board state, or we have found a faster route to an old board
-            // state, so update the map and re-add the current board state for re-evaluation.
-            let mut path: Vec<Swap> = steps.iter().copied().collect();
-            path.push(swap);
-            map.insert(next.clone(), path);
-            states.insert(State::new(next, into));
+    if let Some(path) = &options.verify_target {
+        let dict = Dictionary::from_file(path).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path.display(), err);
+            process::exit(1);
+        });
+        if !into_board.is_valid_waffle(&dict) {
+            eprintln!("The target is not a valid waffle: some of its words are not in {}",
+                      path.display());
+            process::exit(1);
         }
     }
 
-    return None;
-}
+    if options.stats {
+        show_stats(&from_board, &into_board, &options);
+        return;
+    }
 
-fn show_transformation(cur: &WaffleBoard, steps: &[Swap]) {
-    println!("{}", cur.display());
-    if steps.len() == 0 { return; }
-    let step: Swap = steps[0];
-    println!("- swap '{}' at {} with '{}' at {}",
-             cur.get(step.a), step.a,
-             cur.get(step.b), step.b);
-    return show_transformation(&cur.swap(step), &steps[1..]);
-}
+    // Every optimal solution comes from an exhaustive search instead. If there aren't any, the
+    // usual search below explains why.
+    if options.solutions != Solutions::First {
+        let all = find_all_swaps(&from_board, &into_board, options.search.swap_mode);
+        if options.solutions == Solutions::Count {
+            println!("{}", all.len());
+            return;
+        }
+        for (i, path) in all.iter().enumerate() {
+            if i > 0 { println!("{}", options.separator.as_deref().unwrap_or("")); }
+            show_path(&from_board, &into_board, path.clone(), &options);
+        }
+        if all.len() > 0 { return; }
+    }
 
-fn main() -> io::Result<()> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        eprintln!("Expected 2 command line arguments but got {}", args.len() - 1);
-        process::exit(1);
+    // Only ever set by Ctrl-C, when built with the ctrlc feature.
+    let interrupt = Arc::new(AtomicBool::new(false));
+    #[cfg(feature = "ctrlc")]
+    {
+        let handler = interrupt.clone();
+        ctrlc::set_handler(move || handler.store(true, Ordering::Relaxed))
+            .expect("Failed to set the Ctrl-C handler");
     }
 
-    let from_board = WaffleBoard::new(Path::new(&args[1]))?;
-    let into_board = WaffleBoard::new(Path::new(&args[2]))?;
+    let mut profile = Profile::new(options.profile);
+    #[cfg(feature = "serde")]
+    let outcome = match &options.checkpoint {
+        Some(path) => solve_checkpointed(&from_board, &into_board, &options, path, interrupt,
+                                         &mut profile),
+        None => solve_interruptible(&from_board, &into_board, &options.search, interrupt,
+                                    &mut profile),
+    };
+    #[cfg(not(feature = "serde"))]
+    let outcome = solve_interruptible(&from_board, &into_board, &options.search, interrupt,
+                                      &mut profile);
+    if options.profile { eprintln!("{}", profile.report()); }
+    if options.cross_check {
+        if let Err(err) = cross_check(&from_board, &into_board, &outcome, options.search.swap_mode) {
+            eprintln!("Cross-check failed: {}", err);
+            process::exit(1);
+        }
+        eprintln!("Cross-check passed");
+    }
 
-    match find_swaps(&from_board, &into_board) {
-        Some(path) => show_transformation(&from_board, &path),
-        None       => println!("Could not find a path."),
+    let path = match outcome {
+        SolveOutcome::Solved(path) => path,
+        // Show how far the search got, so the time spent isn't entirely wasted. The swaps follow
+        // --format like a solution would, so the note about them goes to stderr unless it's text.
+        SolveOutcome::Interrupted(failure) => {
+            let note = format!("Interrupted; the closest board reached is {:.1}% solved:",
+                               failure.closest.solved_percent(&into_board));
+            match options.format {
+                OutputFormat::Text => println!("{}", note),
+                _ => eprintln!("{}", note),
+            }
+            show_steps(&from_board, &into_board, &failure.closest_path, &options);
+            return;
+        },
+        outcome if options.explain_unsolvable => {
+            println!("{}", diagnose(&from_board, &into_board, &outcome));
+            return;
+        },
+        outcome => {
+            println!("{}", outcome);
+            return;
+        },
     };
 
-    return Ok(());
+    show_path(&from_board, &into_board, path, &options);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fifty swaps which wander away from loyal and back again, completing words along the way.
+    const LONG_SOLUTION: &str = "
+        0,3-0,0 2,2-4,1 3,0-0,0 4,4-4,2 1,0-0,3 3,2-1,2 0,2-3,0 0,0-2,1 2,3-2,4 1,2-2,1
+        4,3-1,2 2,4-1,2 2,4-1,0 4,0-3,0 2,4-4,3 0,0-3,4 2,2-0,3 4,2-0,2 2,0-4,1 4,3-0,4
+        1,2-0,1 2,0-3,4 2,1-0,1 0,1-1,4 1,4-2,2 1,4-0,2 4,2-2,4 2,0-0,0 2,2-0,4 0,4-1,2
+        0,0-4,4 2,2-3,0 0,3-4,1 3,0-2,1 2,3-0,4 1,4-3,4 2,4-0,0 2,1-4,3 2,1-0,1 2,4-1,0
+        2,1-2,4 4,0-0,2 2,1-1,4 4,3-0,1 1,0-2,2 4,4-4,0 2,0-4,4 3,4-1,0 1,4-1,2 0,1-1,0
+    ";
+
+    // testdata/long_transformation* are what show_transformation printed for LONG_SOLUTION when it
+    // still recursed once per swap. No word is right on the first board, so listing those which
+    // are doesn't change them either.
+    #[test]
+    fn long_solutions_are_shown_just_as_they_were_when_recursing() {
+        let into: WaffleBoard = "loyal\ni u o\nlocus\na c e\nchair".parse().unwrap();
+        let steps: Vec<Swap> = LONG_SOLUTION.split_whitespace()
+            .map(|swap| swap.parse().unwrap())
+            .collect();
+        let back: Vec<Swap> = steps.iter().rev().copied().collect();
+        let from = into.apply_all(&back);
+
+        let plain = Options::default();
+        let lines = transformation(&from, &into, &steps, &plain);
+        assert_eq!(lines.join("\n") + "\n", include_str!("../testdata/long_transformation"));
+
+        let explained = Options {
+            explain: true,
+            word_progress: true,
+            separator: Some("---".to_owned()),
+            ..Options::default()
+        };
+        let lines = transformation(&from, &into, &steps, &explained);
+        assert_eq!(lines.join("\n") + "\n",
+                   include_str!("../testdata/long_transformation_explained"));
+    }
 }
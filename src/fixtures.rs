@@ -0,0 +1,147 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Boards, constraint boards, and swaps for tests, written as string literals. Each row of a board
+// is a line of its own, and may be indented to line up with the test; blank lines are skipped.
+// Anything which doesn't parse is a mistake in the test, so these panic rather than return errors.
+
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+use crate::board::{Swap, WaffleBoard};
+use crate::constraints::ConstraintBoard;
+
+fn rows(text: &str) -> String {
+    return text.lines()
+        .map(str::trim)
+        .filter(|line| line.len() > 0)
+        .collect::<Vec<&str>>()
+        .join("\n");
+}
+
+pub fn board(text: &str) -> WaffleBoard {
+    return rows(text).parse().unwrap_or_else(|err| panic!("Bad board fixture: {}", err));
+}
+
+pub fn constraint_board(text: &str) -> ConstraintBoard {
+    return rows(text).parse().unwrap_or_else(|err| panic!("Bad constraint board fixture: {}", err));
+}
+
+// The answer most of the tests work towards: loyal, locus and chair across, and lilac, yucca and
+// loser down.
+pub fn loyal() -> WaffleBoard {
+    return board("
+        loyal
+        i u o
+        locus
+        a c e
+        chair
+    ");
+}
+
+// Swaps written as r,c-r,c, separated by whitespace.
+pub fn swaps(text: &str) -> Vec<Swap> {
+    return text.split_whitespace()
+        .map(|swap| swap.parse().unwrap_or_else(|err| panic!("Bad swap fixture: {}", err)))
+        .collect();
+}
+
+// xorshift64, for tests which want plenty of varied boards without listing them all. It's seeded,
+// so a test sees the same ones every time it runs.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        return Self { state: seed };
+    }
+
+    // A number from 0 up to, but not including, n.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        return (self.state % n as u64) as usize;
+    }
+}
+
+// A path in the temporary directory for a test to write to, removed once the test is done with
+// it, whether it passes or not. Tests run at the same time, so each should use a different name.
+pub struct TempPath {
+    path: PathBuf,
+}
+
+impl TempPath {
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("waffler-{}-{}", process::id(), name));
+        return Self { path: path };
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Coord;
+
+    #[test]
+    fn board_ignores_indentation() {
+        let loyal = board("
+            loyal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        assert_eq!(loyal.size(), (5, 5));
+        assert_eq!(loyal.get(Coord{ row: 2, col: 4 }), 's');
+    }
+
+    #[test]
+    fn constraint_board_keeps_known_letters() {
+        let known = constraint_board("
+            LOYAL
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        assert_eq!(known.get(0, 0), Some('l'));
+        assert_eq!(known.get(2, 0), None);
+    }
+
+    #[test]
+    fn swaps_reads_each_pair() {
+        let parsed = swaps("0,0-2,4 1,0-3,4");
+        assert_eq!(parsed, vec![
+            Swap::new(Coord{ row: 0, col: 0 }, Coord{ row: 2, col: 4 }),
+            Swap::new(Coord{ row: 1, col: 0 }, Coord{ row: 3, col: 4 }),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad board fixture")]
+    fn board_panics_on_a_bad_fixture() {
+        board("lo\nyal");
+    }
+}
@@ -0,0 +1,101 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Replays a solution one swap at a time, along with which cells are right after each swap. This is
+// what waffle watch draws, kept apart from the drawing so anything can show it.
+
+use crate::board::{CellStatus, Swap, WaffleBoard};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    // How many swaps have been made, so 0 for the starting board.
+    pub step: usize,
+    // The swap which led to this board, if any.
+    pub swap: Option<Swap>,
+    pub board: WaffleBoard,
+    pub status: Vec<Vec<CellStatus>>,
+}
+
+// The starting board, then the board after each swap in turn.
+pub struct Frames<'a> {
+    cur: WaffleBoard,
+    into: &'a WaffleBoard,
+    steps: &'a [Swap],
+    step: usize,
+}
+
+impl<'a> Frames<'a> {
+    pub fn new(from: &WaffleBoard, into: &'a WaffleBoard, steps: &'a [Swap]) -> Self {
+        return Self { cur: from.clone(), into: into, steps: steps, step: 0 };
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        if self.step > self.steps.len() { return None; }
+        let swap = if self.step == 0 { None } else { Some(self.steps[self.step - 1]) };
+        if let Some(swap) = swap { self.cur = self.cur.swap(swap); }
+
+        let frame = Frame {
+            step: self.step,
+            swap: swap,
+            board: self.cur.clone(),
+            status: self.cur.match_status(self.into),
+        };
+        self.step += 1;
+        return Some(frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{board, loyal, swaps};
+
+    #[test]
+    fn each_frame_follows_one_more_swap() {
+        let from = board("
+            oylal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        let into = loyal();
+        let steps = swaps("0,0-0,2 0,1-0,2");
+        let frames: Vec<Frame> = Frames::new(&from, &into, &steps).collect();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames.iter().map(|frame| frame.step).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(frames[0].swap, None);
+        assert_eq!(frames[0].board, from);
+        assert_eq!(frames[1].swap, Some(steps[0]));
+        assert_eq!(frames[1].board, from.swap(steps[0]));
+        assert_eq!(frames[2].board, into);
+
+        let first_row = |frame: &Frame| frame.status[0].clone();
+        let (correct, wrong) = (CellStatus::Correct, CellStatus::Wrong);
+        assert_eq!(first_row(&frames[0]), [wrong, wrong, wrong, correct, correct]);
+        assert_eq!(first_row(&frames[1]), [correct, wrong, wrong, correct, correct]);
+        assert_eq!(frames[2].status, into.match_status(&into));
+    }
+
+    #[test]
+    fn a_solved_board_has_just_the_one_frame() {
+        let into = loyal();
+        let frames: Vec<Frame> = Frames::new(&into, &into, &[]).collect();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].board, into);
+    }
+}
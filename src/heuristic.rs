@@ -0,0 +1,62 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Estimates of how many swaps are left, for find_swaps to decide which boards to explore first.
+// As long as an estimate never exceeds the real number of swaps (ie, it's admissible), the search
+// returns a shortest path. One that overestimates may make the search quicker, but its paths may
+// be longer than they need to be.
+
+use std::collections::HashMap;
+
+use crate::board::WaffleBoard;
+
+pub trait Heuristic {
+    fn estimate(&self, cur: &WaffleBoard, target: &WaffleBoard) -> usize;
+}
+
+// A swap fixes at most two cells, so at least half the wrong cells' worth of swaps are needed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffHalf;
+
+impl Heuristic for DiffHalf {
+    fn estimate(&self, cur: &WaffleBoard, target: &WaffleBoard) -> usize {
+        return cur.hamming_distance(target).div_ceil(2);
+    }
+}
+
+// Think of each wrong cell as pointing at a cell its letter belongs in: the wrong cells then split
+// up into cycles, and a cycle of n cells takes n - 1 swaps, so the wrong cells less the number of
+// cycles is what's needed. Only a pair of cells holding each other's letters can make a cycle of
+// two, and every other cycle is at least three long, which bounds how many cycles there can be.
+// This is never less than DiffHalf, and is more whenever some cells can't be fixed in pairs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CycleBound;
+
+impl Heuristic for CycleBound {
+    fn estimate(&self, cur: &WaffleBoard, target: &WaffleBoard) -> usize {
+        let wrong = cur.diff(target);
+
+        // How many wrong cells hold each letter while needing each other letter.
+        let mut moves: HashMap<(char, char), usize> = HashMap::new();
+        for &coord in &wrong {
+            *moves.entry((cur.get(coord), target.get(coord))).or_default() += 1;
+        }
+        let pairs: usize = moves.iter()
+            .filter(|&(&(have, want), _)| have < want)
+            .map(|(&(have, want), &count)| count.min(moves.get(&(want, have)).copied().unwrap_or(0)))
+            .sum();
+
+        let cycles = pairs + (wrong.len() - 2 * pairs) / 3;
+        return wrong.len() - cycles;
+    }
+}
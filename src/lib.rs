@@ -0,0 +1,28 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+pub mod answers;
+pub mod auto;
+pub mod board;
+pub mod cache;
+pub mod constraints;
+pub mod csv;
+pub mod dictionary;
+#[cfg(feature = "edit")]
+pub mod editor;
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod frames;
+pub mod heuristic;
+pub mod profile;
+pub mod search;
@@ -0,0 +1,47 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+// Accumulates how long the search spends in each of its hot sections, and how often it enters
+// them. A disabled profile just runs the code without timing it.
+#[derive(Debug, Default)]
+pub struct Profile {
+    enabled: bool,
+    sections: BTreeMap<&'static str, (usize, Duration)>,
+}
+
+impl Profile {
+    pub fn new(enabled: bool) -> Self {
+        return Self { enabled: enabled, sections: BTreeMap::new() };
+    }
+
+    pub fn time<T>(&mut self, section: &'static str, f: impl FnOnce() -> T) -> T {
+        if !self.enabled { return f(); }
+        let start = Instant::now();
+        let ret = f();
+        let entry = self.sections.entry(section).or_default();
+        entry.0 += 1;
+        entry.1 += start.elapsed();
+        return ret;
+    }
+
+    pub fn report(&self) -> String {
+        let mut lines = vec![format!("{:<10} {:>10} {:>12}", "section", "calls", "total")];
+        for (section, (calls, total)) in &self.sections {
+            lines.push(format!("{:<10} {:>10} {:>12.3?}", section, calls, total));
+        }
+        return lines.join("\n");
+    }
+}
@@ -0,0 +1,1450 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+use std::{cmp, fmt};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "serde")]
+use std::path::Path;
+#[cfg(feature = "serde")]
+use std::{fs, io};
+
+use itertools::Itertools;
+
+use crate::board::{Coord, Swap, SwapMode, WaffleBoard};
+use crate::heuristic::{CycleBound, DiffHalf, Heuristic};
+use crate::profile::Profile;
+
+#[derive(Clone, Debug)]
+struct State {
+    cur: WaffleBoard,
+    // The swaps made to get here, plus the estimate of how many more are needed.
+    cost: usize,
+    estimate: usize,
+    // Boards which tie on cost are ordered by checksum before their cells, as the checksums
+    // almost always tell different boards apart without looking at every cell.
+    checksum: u64,
+}
+
+impl State {
+    fn new(cur: WaffleBoard, steps: usize, estimate: usize) -> Self {
+        let checksum = cur.checksum();
+        return Self {
+            cur: cur,
+            cost: steps + estimate,
+            estimate: estimate,
+            checksum: checksum,
+        };
+    }
+}
+
+impl PartialEq for State {
+    fn eq(&self, other: &Self) -> bool {
+        return self.checksum == other.checksum && self.cur == other.cur;
+    }
+}
+
+impl Eq for State {}
+
+// Cheapest first, and of those, the ones closest to the target. The rest of the order only has to
+// be consistent, so that the frontier can tell boards apart.
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        return self.cost.cmp(&other.cost)
+            .then(self.estimate.cmp(&other.estimate))
+            .then(self.checksum.cmp(&other.checksum))
+            .then_with(|| self.cur.cells().cmp(other.cur.cells()));
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchOptions {
+    pub swap_mode: SwapMode,
+    // Key the boards seen so far by the boards themselves, rather than by their content_hash.
+    // That takes a lot more memory, but rules out the slim chance of two boards colliding.
+    pub exact_keys: bool,
+    // Treat boards which only differ by exchanging rows (or columns) that are identical in the
+    // target as the same board, so only one of them gets explored.
+    pub canonicalize: bool,
+}
+
+// Groups of rows, and groups of columns, which are identical in the target. Exchanging two of them
+// wholesale leaves the target as it is, so two boards related by such an exchange are exactly the
+// same distance from it.
+struct Symmetries {
+    rows: Vec<Vec<usize>>,
+    cols: Vec<Vec<usize>>,
+}
+
+impl Symmetries {
+    fn new(into: &WaffleBoard) -> Self {
+        let (rows, cols) = into.size();
+        let groups = |lines: Vec<Vec<char>>| -> Vec<Vec<usize>> {
+            return lines.into_iter()
+                .enumerate()
+                .into_group_map_by(|(_, line)| line.clone())
+                .into_values()
+                .filter(|group| group.len() > 1)
+                .map(|group| group.into_iter().map(|(i, _)| i).sorted().collect())
+                .sorted()
+                .collect();
+        };
+
+        return Self {
+            rows: groups(into.cells().to_vec()),
+            cols: groups((0..cols)
+                .map(|col| (0..rows).map(|row| into.cells()[row][col]).collect())
+                .collect()),
+        };
+    }
+
+    // Sorts the contents of each group, so that every board in a family of equivalent boards ends
+    // up as the same one. Applying the row and column exchanges one after the other won't always
+    // merge the whole family, but it never merges boards that aren't equivalent.
+    fn canonical(&self, board: &WaffleBoard) -> WaffleBoard {
+        let mut cells = board.cells().to_vec();
+        for group in &self.rows {
+            let contents: Vec<Vec<char>> = group.iter().map(|&row| cells[row].clone()).sorted().collect();
+            for (&row, content) in group.iter().zip(contents) { cells[row] = content; }
+        }
+        for group in &self.cols {
+            let column = |col: usize| -> Vec<char> { cells.iter().map(|line| line[col]).collect() };
+            let contents: Vec<Vec<char>> = group.iter().map(|&col| column(col)).sorted().collect();
+            for (&col, content) in group.iter().zip(contents) {
+                for (row, c) in content.into_iter().enumerate() { cells[row][col] = c; }
+            }
+        }
+        return board.sized_like(cells);
+    }
+}
+
+// How the search remembers the boards it has seen: by a hash of each, or with exact_keys by the
+// board itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum VisitedKey {
+    Hash(u128),
+    Exact(WaffleBoard),
+}
+
+// Paths longer than this are abandoned rather than searched any further.
+pub const MAX_SWAPS: usize = 10;
+
+// Why the search gave up: the closest it got to the target and how it got there, whether it
+// abandoned any paths for being longer than MAX_SWAPS, and whether it was interrupted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchFailure {
+    pub closest: WaffleBoard,
+    pub closest_path: Vec<Swap>,
+    pub hit_limit: bool,
+    pub interrupted: bool,
+}
+
+impl SearchFailure {
+    fn new(closest: &WaffleBoard) -> Self {
+        return Self {
+            closest: closest.clone(),
+            closest_path: Vec::new(),
+            hit_limit: false,
+            interrupted: false,
+        };
+    }
+}
+
+// How much work a search did: how many boards it explored, and the most boards it ever had waiting
+// to be explored, or remembered having seen. The latter two are what take up its memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub explored: usize,
+    pub peak_frontier: usize,
+    pub peak_visited: usize,
+    // How many swaps between two cells holding the same letter were passed over, by how many swaps
+    // had already been made. Those swaps leave the board as it is, so are never worth making.
+    pub trivial_by_step: Vec<usize>,
+}
+
+// Everything the search needs to carry on from where it is: the target, the best known path to
+// every board seen so far, and the boards still waiting to be explored.
+pub struct SearchState<H = DiffHalf> {
+    from: WaffleBoard,
+    into: WaffleBoard,
+    options: SearchOptions,
+    symmetries: Option<Symmetries>,
+    map: HashMap<VisitedKey, Vec<Swap>>,
+    states: BTreeSet<State>,
+    // When canonicalizing, the shortest path length to each family of equivalent boards. The map
+    // above is still keyed by the actual boards, as the paths are only valid for those.
+    canonical_lens: HashMap<VisitedKey, usize>,
+    failure: SearchFailure,
+    heuristic: H,
+    // Set from elsewhere, such as a signal handler, to stop the search early.
+    interrupt: Option<Arc<AtomicBool>>,
+    stats: SearchStats,
+}
+
+impl SearchState {
+    pub fn new(from: &WaffleBoard, into: &WaffleBoard, options: &SearchOptions) -> Self {
+        return Self::with_heuristic(from, into, options, DiffHalf);
+    }
+}
+
+impl<H: Heuristic> SearchState<H> {
+    pub fn with_heuristic(from: &WaffleBoard, into: &WaffleBoard, options: &SearchOptions,
+                          heuristic: H) -> Self {
+        let mut ret = Self {
+            from: from.clone(),
+            into: into.clone(),
+            options: *options,
+            symmetries: options.canonicalize.then(|| Symmetries::new(into)),
+            map: HashMap::new(),
+            states: BTreeSet::new(),
+            canonical_lens: HashMap::new(),
+            failure: SearchFailure::new(from),
+            heuristic: heuristic,
+            interrupt: None,
+            stats: SearchStats::default(),
+        };
+        ret.map.insert(ret.key(from), Vec::new());
+        // There's nothing to search when the target is out of reach whatever the swaps.
+        if can_reach(from, into, options.swap_mode) {
+            ret.states.insert(State::new(from.clone(), 0, ret.estimate(from)));
+        }
+        if let Some(symmetries) = &ret.symmetries {
+            let key = ret.key(&symmetries.canonical(from));
+            ret.canonical_lens.insert(key, 0);
+        }
+        return ret;
+    }
+
+    // The work done so far. A search picked up from a checkpoint only counts what it has done since.
+    pub fn stats(&self) -> &SearchStats {
+        return &self.stats;
+    }
+
+    // The heuristic's estimate, raised to moves_bound under the restricted modes, which it can't
+    // know about.
+    fn estimate(&self, board: &WaffleBoard) -> usize {
+        let bound = moves_bound(board, &self.into, self.options.swap_mode);
+        return self.heuristic.estimate(board, &self.into).max(bound);
+    }
+
+    fn key(&self, board: &WaffleBoard) -> VisitedKey {
+        return match self.options.exact_keys {
+            true => VisitedKey::Exact(board.clone()),
+            false => VisitedKey::Hash(board.content_hash()),
+        };
+    }
+
+    // Gives up as soon as flag is set, as if there were nothing left to explore.
+    pub fn interrupt_on(&mut self, flag: Arc<AtomicBool>) {
+        self.interrupt = Some(flag);
+    }
+
+    // Whether this is a search for the given puzzle, with the given options.
+    pub fn is_for(&self, from: &WaffleBoard, into: &WaffleBoard, options: &SearchOptions) -> bool {
+        return &self.from == from && &self.into == into && &self.options == options;
+    }
+
+    fn get_swaps(&self, board: &WaffleBoard, profile: &mut Profile) -> Vec<Swap> {
+        let differences = profile.time("score", || board.diff(&self.into));
+        // A single wrong cell can only happen when the boards hold different letters, and then no
+        // swap can fix it.
+        if differences.len() < 2 { return Vec::new(); }
+        // Under the restricted modes a letter may have to detour through cells which are already
+        // right, so every cell is a candidate, not just the wrong ones.
+        let cells = match self.options.swap_mode {
+            SwapMode::Any => differences,
+            _ => board.active_coords().to_vec(),
+        };
+        // Find all unique possible swaps; note that Swap::new already acts as a sorted pair of
+        // coordintes, so any two swaps of point a and point b are identical.
+        let uniques: HashSet<Swap> = cells.into_iter()
+            .combinations(2)
+            .map(|pair| Swap::new(pair[0], pair[1]))
+            .filter(|swap| !swap.touches_gap(board.layout()))
+            .filter(|&swap| self.options.swap_mode.allows(swap))
+            .collect();
+        // sort the possible swaps, so that the output will be deterministic.
+        let mut sorted: Vec<Swap> = uniques.into_iter().collect();
+        sorted.sort();
+        return sorted;
+    }
+
+    // Searches until a path is found or there's nothing left to explore. The state is passed to
+    // checkpoint before each board is explored, which is the time to save it if it's wanted; as
+    // the search is deterministic, one picked up from there finds the same path as this would.
+    //
+    // The search's hot sections are timed with profile: scoring boards against the target
+    // ("score"), making new boards ("swap"), the visited map ("map") and the frontier
+    // ("frontier").
+    pub fn run(&mut self, profile: &mut Profile,
+               checkpoint: &mut dyn FnMut(&Self)) -> Result<Vec<Swap>, SearchFailure> {
+        // BTreeSet is a sorted set. pop_first  will return the lowest-cost item. That means, we
+        // will continually find the (or a) board on the shortest path to the target, as far as the
+        // heuristic can tell. If the heuristic never overestimates, the first path to reach the
+        // target is as short as any.
+        loop {
+            // Taken before anything else, so that however the search stops, the peaks cover
+            // everything it still remembers.
+            self.stats.peak_frontier = self.stats.peak_frontier.max(self.states.len());
+            self.stats.peak_visited = self.stats.peak_visited.max(self.map.len());
+            checkpoint(self);
+            if self.interrupt.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+                self.failure.interrupted = true;
+                break;
+            }
+            let cur = match profile.time("frontier", || self.states.pop_first()) {
+                Some(State { cur, .. }) => cur,
+                None => break,
+            };
+            self.stats.explored += 1;
+
+            // A reference to the old path, so we can check its length.
+            let prev_path = profile.time("map", || self.map.get(&self.key(&cur)).unwrap());
+            if prev_path.len() > MAX_SWAPS {
+                self.failure.hit_limit = true;
+                continue;
+            }
+
+            // If it's not already too long, we can take the time to copy it into the local scope.
+            let steps: Vec<Swap> = prev_path.to_vec();
+            // Every step the search reaches gets a count, even if it's zero.
+            let trivial = &mut self.stats.trivial_by_step;
+            if trivial.len() <= steps.len() { trivial.resize(steps.len() + 1, 0); }
+            let cur_score = profile.time("score", || cur.score(&self.into));
+            if cur_score == 0 { return Ok(steps); }
+            if cur_score < self.failure.closest.score(&self.into) {
+                self.failure.closest = cur.clone();
+            }
+
+            for swap in self.get_swaps(&cur, profile) {
+                if cur.get(swap.a) == cur.get(swap.b) {
+                    self.stats.trivial_by_step[steps.len()] += 1;
+                    continue;
+                }
+                let next = profile.time("swap", || cur.swap(swap));
+
+                // If this swap does not improve our position, skip it. When any two cells can be
+                // swapped, a shortest path never has to make such a swap.
+                let improves = profile.time("score", || next.score(&self.into)) < cur_score;
+                if !improves && self.options.swap_mode == SwapMode::Any { continue; }
+
+                // Under the restricted modes detours can't be ruled out like that, but a board
+                // which can no longer reach the target within MAX_SWAPS is no use either.
+                let bound = moves_bound(&next, &self.into, self.options.swap_mode);
+                if steps.len() + 1 + bound > MAX_SWAPS {
+                    self.failure.hit_limit = true;
+                    continue;
+                }
+
+                let key = profile.time("map", || self.key(&next));
+                let prev_len = profile.time("map", || self.map.get(&key).map(Vec::len));
+
+                // If we've already seen this state before, and the old path is no shorter than the
+                // current path (ie, we have no improvement), then continue.
+                if prev_len.is_some() && prev_len.unwrap() <= steps.len() + 1 { continue; }
+
+                // Likewise if we've already reached an equivalent board at least as quickly.
+                if let Some(symmetries) = &self.symmetries {
+                    let canonical = self.key(&symmetries.canonical(&next));
+                    let prev_len = self.canonical_lens.get(&canonical).copied();
+                    if prev_len.is_some() && prev_len.unwrap() <= steps.len() + 1 { continue; }
+                    self.canonical_lens.insert(canonical, steps.len() + 1);
+                }
+
+                // Otherwise we have a new board state, or we have found a faster route to an old
+                // board state, so update the map and re-add the current board state for
+                // re-evaluation.
+                let mut path: Vec<Swap> = steps.to_vec();
+                path.push(swap);
+                profile.time("map", || self.map.insert(key, path));
+                let estimate = profile.time("score", || self.estimate(&next));
+                let state = State::new(next, steps.len() + 1, estimate);
+                profile.time("frontier", || self.states.insert(state));
+            }
+        }
+
+        let mut failure = self.failure.clone();
+        failure.closest_path = self.map[&self.key(&failure.closest)].clone();
+        return Err(failure);
+    }
+}
+
+// The saved form of a SearchState. JSON objects can only have strings for keys, so the maps are
+// kept as lists of pairs, and the rest is worked out again from the target when loading.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedSearch {
+    from: WaffleBoard,
+    into: WaffleBoard,
+    options: SearchOptions,
+    map: Vec<(VisitedKey, Vec<Swap>)>,
+    states: Vec<WaffleBoard>,
+    canonical_lens: Vec<(VisitedKey, usize)>,
+    closest: WaffleBoard,
+    hit_limit: bool,
+}
+
+#[cfg(feature = "serde")]
+impl<H: Heuristic> SearchState<H> {
+    // Writes the state to a file beside path before moving it into place, so that being
+    // interrupted part way through never leaves a half written checkpoint.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let saved = SavedSearch {
+            from: self.from.clone(),
+            into: self.into.clone(),
+            options: self.options,
+            map: self.map.iter().map(|(board, path)| (board.clone(), path.clone())).collect(),
+            states: self.states.iter().map(|state| state.cur.clone()).collect(),
+            canonical_lens: self.canonical_lens.iter()
+                .map(|(board, &len)| (board.clone(), len))
+                .collect(),
+            closest: self.failure.closest.clone(),
+            hit_limit: self.failure.hit_limit,
+        };
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        fs::write(&temp, serde_json::to_string(&saved)?)?;
+        return fs::rename(&temp, path);
+    }
+
+    // The heuristic isn't saved, so it has to be the same one the state was saved with.
+    pub fn load(path: &Path, heuristic: H) -> io::Result<Self> {
+        let saved: SavedSearch = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let mut ret = Self {
+            symmetries: saved.options.canonicalize.then(|| Symmetries::new(&saved.into)),
+            from: saved.from,
+            into: saved.into,
+            options: saved.options,
+            map: saved.map.into_iter().collect(),
+            states: BTreeSet::new(),
+            canonical_lens: saved.canonical_lens.into_iter().collect(),
+            failure: SearchFailure { hit_limit: saved.hit_limit, ..SearchFailure::new(&saved.closest) },
+            heuristic: heuristic,
+            interrupt: None,
+            stats: SearchStats::default(),
+        };
+        for board in saved.states {
+            let estimate = ret.estimate(&board);
+            let steps = ret.map[&ret.key(&board)].len();
+            ret.states.insert(State::new(board, steps, estimate));
+        }
+        return Ok(ret);
+    }
+}
+
+// Finds a shortest path from from to into, guided by heuristic. DiffHalf is the default, and what
+// SearchState::new uses; any admissible heuristic gives a path as short, but one which isn't may
+// give a longer one.
+pub fn find_swaps<H: Heuristic>(from: &WaffleBoard, into: &WaffleBoard, options: &SearchOptions,
+                                heuristic: H, profile: &mut Profile) -> Option<Vec<Swap>> {
+    return search(from, into, options, heuristic, None, profile).ok();
+}
+
+fn search<H: Heuristic>(from: &WaffleBoard, into: &WaffleBoard, options: &SearchOptions,
+                        heuristic: H, interrupt: Option<Arc<AtomicBool>>,
+                        profile: &mut Profile) -> Result<Vec<Swap>, SearchFailure> {
+    if let Some(steps) = greedy_shortcut(from, into, options.swap_mode) { return Ok(steps); }
+
+    let mut state = SearchState::with_heuristic(from, into, options, heuristic);
+    if let Some(flag) = interrupt { state.interrupt_on(flag); }
+    return state.run(profile, &mut |_| ());
+}
+
+// A swap can fix at most two cells, so if the greedy chain never has to settle for fixing just one,
+// nothing can beat it and there's no need to search. Returns the chain in that case, or None if a
+// search is needed after all.
+pub fn greedy_shortcut(from: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> Option<Vec<Swap>> {
+    let steps = find_swaps_greedy(from, into, mode)?;
+    return if steps.len() == from.score(into).div_ceil(2) { Some(steps) } else { None };
+}
+
+// Makes the swaps already made, then finds the rest of the way to into from there. If already is
+// the start of a shortest path, so is already followed by what this returns. None if there's no
+// way from there, or if already tries to swap a gap.
+pub fn continue_solve(from: &WaffleBoard, into: &WaffleBoard, already: &[Swap]) -> Option<Vec<Swap>> {
+    let cur = from.try_apply_all(already).ok()?;
+    return find_swaps(&cur, into, &SearchOptions::default(), DiffHalf, &mut Profile::new(false));
+}
+
+// Solves as find_swaps does, along with how many cells are wrong before the first swap and after
+// each one, for charting how a solve goes. Along a shortest path this never goes up.
+pub fn solve_with_trace(from: &WaffleBoard, into: &WaffleBoard) -> Option<(Vec<Swap>, Vec<usize>)> {
+    let options = SearchOptions::default();
+    let steps = find_swaps(from, into, &options, DiffHalf, &mut Profile::new(false))?;
+    let mut cur = from.clone();
+    let mut trace = vec![cur.score(into)];
+    for &swap in &steps {
+        cur.swap_mut(swap);
+        trace.push(cur.score(into));
+    }
+    return Some((steps, trace));
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveOutcome {
+    Solved(Vec<Swap>),
+    // The boards hold different letters, so no sequence of swaps can turn one into the other.
+    LettersDiffer,
+    // The boards hold the same letters, but the search gave up before finding a path.
+    NotFound(SearchFailure),
+    // The search was stopped before it finished.
+    Interrupted(SearchFailure),
+}
+
+impl SolveOutcome {
+    pub fn from_search(result: Result<Vec<Swap>, SearchFailure>) -> Self {
+        return match result {
+            Ok(steps) => Self::Solved(steps),
+            Err(failure) if failure.interrupted => Self::Interrupted(failure),
+            Err(failure) => Self::NotFound(failure),
+        };
+    }
+}
+
+impl fmt::Display for SolveOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Self::Solved(steps) => write!(f, "Solved in {} swaps", steps.len()),
+            Self::LettersDiffer => write!(f, "Unsolvable: letter sets differ"),
+            // Only a search which never had to give up on a path has shown there isn't one.
+            Self::NotFound(failure) if failure.hit_limit => write!(f,
+                "No path found within {} swaps (same letters, but longer paths were not searched)",
+                MAX_SWAPS),
+            Self::NotFound(_) => write!(f, "No path found (same letters but unreachable)"),
+            Self::Interrupted(_) => write!(f, "Interrupted before finding a path"),
+        };
+    }
+}
+
+// Solves the puzzle as the binaries do. Rather than the default DiffHalf, the search is guided by
+// CycleBound, which is just as admissible but far tighter: on src/testdata it explores 45 boards
+// where DiffHalf explores 4703.
+pub fn solve(from: &WaffleBoard, into: &WaffleBoard, options: &SearchOptions,
+             profile: &mut Profile) -> SolveOutcome {
+    if from.letter_counts() != into.letter_counts() { return SolveOutcome::LettersDiffer; }
+    return SolveOutcome::from_search(search(from, into, options, CycleBound, None, profile));
+}
+
+// The same as solve, but stopping early once interrupt is set.
+pub fn solve_interruptible(from: &WaffleBoard, into: &WaffleBoard, options: &SearchOptions,
+                           interrupt: Arc<AtomicBool>, profile: &mut Profile) -> SolveOutcome {
+    if from.letter_counts() != into.letter_counts() { return SolveOutcome::LettersDiffer; }
+    let result = search(from, into, options, CycleBound, Some(interrupt), profile);
+    return SolveOutcome::from_search(result);
+}
+
+// Explains why solve couldn't turn from into into: which letters one board has more of than the
+// other, or how close the search got and whether it ran into its length limit.
+pub fn diagnose(from: &WaffleBoard, into: &WaffleBoard, outcome: &SolveOutcome) -> String {
+    let mut lines = vec![outcome.to_string()];
+    match outcome {
+        SolveOutcome::Solved(_) => (),
+        SolveOutcome::LettersDiffer => {
+            let have = from.letter_counts();
+            let want = into.letter_counts();
+            let letters: BTreeSet<char> = have.keys().chain(want.keys()).copied().collect();
+            for letter in letters {
+                let have = have.get(&letter).copied().unwrap_or(0);
+                let want = want.get(&letter).copied().unwrap_or(0);
+                if have > want {
+                    lines.push(format!("surplus '{}': {} too many", letter, have - want));
+                }
+                if have < want {
+                    lines.push(format!("deficit '{}': {} too few", letter, want - have));
+                }
+            }
+        },
+        SolveOutcome::NotFound(failure) | SolveOutcome::Interrupted(failure) => {
+            if failure.interrupted {
+                lines.push("the search was interrupted".to_owned());
+            } else if failure.hit_limit {
+                lines.push(format!("abandoned paths longer than {} swaps", MAX_SWAPS));
+            } else {
+                lines.push("every path was explored without reaching the target".to_owned());
+            }
+            let solved = failure.closest.solved_percent(into);
+            lines.push(format!("best reached: {:.1}% solved", solved));
+        },
+    }
+    return lines.join("\n");
+}
+
+// Every swap between two incorrect cells which leaves the board closer to the target. When any two
+// cells can be swapped, an optimal solution only ever makes swaps of this kind.
+fn improving_swaps(board: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> Vec<Swap> {
+    // Both cells start out wrong, so the swap helps as long as either of them ends up right.
+    return board.diff(into).into_iter()
+        .tuple_combinations()
+        .filter(|&(a, b)| board.get(a) == into.get(b) || board.get(b) == into.get(a))
+        .map(|(a, b)| Swap::new(a, b))
+        .filter(|swap| !swap.touches_gap(board.layout()))
+        .filter(|&swap| mode.allows(swap))
+        .collect();
+}
+
+// The swaps a shortest path might make next. Under the restricted modes a letter may have to take
+// a detour through cells which are already right, so that's any swap the mode allows between two
+// different letters, rather than just the improving ones.
+fn candidate_swaps(board: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> Vec<Swap> {
+    if mode == SwapMode::Any { return improving_swaps(board, into, mode); }
+    return board.active_coords().iter()
+        .copied()
+        .tuple_combinations()
+        .map(|(a, b)| Swap::new(a, b))
+        .filter(|&swap| mode.allows(swap) && board.get(swap.a) != board.get(swap.b))
+        .collect();
+}
+
+// A lower bound on the swaps needed under a restricted mode, which unlike the heuristics counts the
+// detours. Every swap moves two letters, each to a cell the mode lets it reach in one go, and each
+// wrong letter has to end up in some cell which needs it: a cell it can't reach in one go takes at
+// least two moves.
+fn moves_bound(board: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> usize {
+    if mode == SwapMode::Any { return 0; }
+    let moves: usize = board.diff(into).into_iter()
+        .map(|from| {
+            let reachable = |to: Coord| mode.allows(Swap::new(from, to));
+            let needs = |to: &&Coord| to != &&from && into.get(**to) == board.get(from);
+            match into.active_coords().iter().filter(needs).any(|&to| reachable(to)) {
+                true => 1,
+                false => 2,
+            }
+        })
+        .sum();
+    return moves.div_ceil(2);
+}
+
+// Under SwapMode::Row letters never leave their row, so every row has to hold the same letters as
+// it does in into, and likewise for the columns under SwapMode::Col. Under the other modes any
+// arrangement of the letters can be reached.
+pub fn can_reach(from: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> bool {
+    let lines = |board: &WaffleBoard, by_row: bool| -> Vec<Vec<char>> {
+        let mut lines = vec![Vec::new(); if by_row { board.size().0 } else { board.size().1 }];
+        for &coord in board.active_coords() {
+            lines[if by_row { coord.row } else { coord.col }].push(board.get(coord));
+        }
+        lines.iter_mut().for_each(|line| line.sort());
+        return lines;
+    };
+    return match mode {
+        SwapMode::Row => lines(from, true) == lines(into, true),
+        SwapMode::Col => lines(from, false) == lines(into, false),
+        SwapMode::Any | SwapMode::Line => from.letter_counts() == into.letter_counts(),
+    };
+}
+
+// Repeatedly makes whichever improving swap fixes the most cells, without ever backtracking. This
+// is quick but may miss the shortest solution, and returns None if it gets stuck.
+pub fn find_swaps_greedy(from: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> Option<Vec<Swap>> {
+    let mut cur = from.clone();
+    let mut steps = Vec::new();
+    while cur.score(into) > 0 {
+        let fixed = |swap: &Swap| {
+            let next = cur.swap(*swap);
+            [swap.a, swap.b].iter().filter(|&&coord| next.get(coord) == into.get(coord)).count()
+        };
+        // max_by_key returns the last of several equal options, so go backwards to get the first.
+        let swap = improving_swaps(&cur, into, mode).into_iter().rev().max_by_key(fixed)?;
+        cur = cur.swap(swap);
+        steps.push(swap);
+    }
+    return Some(steps);
+}
+
+// Shortens a solution from a quicker, non-optimal solver by dropping any swap, or pair of swaps,
+// which it still reaches into without, until there are none left to drop. This is often, but not
+// always, as short as a full search would find. Steps which don't reach into come back unchanged.
+pub fn minimize(from: &WaffleBoard, into: &WaffleBoard, steps: &[Swap]) -> Vec<Swap> {
+    let reaches = |steps: &[Swap]| from.try_apply_all(steps).is_ok_and(|board| board == *into);
+    let mut ret = steps.to_vec();
+    if !reaches(&ret) { return ret; }
+    while let Some(shorter) = drop_redundant(&ret, &reaches) {
+        ret = shorter;
+    }
+    return ret;
+}
+
+// The first of steps with one swap left out, then with two, which still reaches the target.
+fn drop_redundant(steps: &[Swap], reaches: &dyn Fn(&[Swap]) -> bool) -> Option<Vec<Swap>> {
+    for i in 0..steps.len() {
+        let mut shorter = steps.to_vec();
+        shorter.remove(i);
+        if reaches(&shorter) { return Some(shorter); }
+    }
+    for (i, j) in (0..steps.len()).tuple_combinations() {
+        let mut shorter = steps.to_vec();
+        shorter.remove(j);
+        shorter.remove(i);
+        if reaches(&shorter) { return Some(shorter); }
+    }
+    return None;
+}
+
+// The fewest swaps needed to get from each board to into, worked out as they're asked for and
+// remembered.
+struct Distances<'a> {
+    into: &'a WaffleBoard,
+    mode: SwapMode,
+    known: HashMap<WaffleBoard, Option<usize>>,
+    // Under the restricted modes, the most swaps each board is known not to reach into within.
+    short: HashMap<WaffleBoard, usize>,
+}
+
+impl<'a> Distances<'a> {
+    fn new(into: &'a WaffleBoard, mode: SwapMode) -> Self {
+        return Self { into: into, mode: mode, known: HashMap::new(), short: HashMap::new() };
+    }
+
+    // None if into can't be reached at all, or under the restricted modes, if it can't be reached
+    // within MAX_SWAPS.
+    fn get(&mut self, board: &WaffleBoard) -> Option<usize> {
+        if let Some(&known) = self.known.get(board) { return known; }
+
+        let ret = match self.mode {
+            _ if board.score(self.into) == 0 => Some(0),
+            SwapMode::Any => improving_swaps(board, self.into, self.mode).into_iter()
+                .filter_map(|swap| self.get(&board.swap(swap)))
+                .min()
+                .map(|dist| dist + 1),
+            // Detours don't bring the board any closer, so paths can go round in circles. Look
+            // for a path of each length in turn instead.
+            _ if !can_reach(board, self.into, self.mode) => None,
+            _ => (1..=MAX_SWAPS).find(|&limit| self.within(board, limit)),
+        };
+
+        self.known.insert(board.clone(), ret);
+        return ret;
+    }
+
+    // Whether into can be reached from board in at most limit swaps.
+    fn within(&mut self, board: &WaffleBoard, limit: usize) -> bool {
+        if let Some(&known) = self.known.get(board) { return known.is_some_and(|dist| dist <= limit); }
+        if self.mode == SwapMode::Any { return self.get(board).is_some_and(|dist| dist <= limit); }
+        if board == self.into { return true; }
+        let bound = CycleBound.estimate(board, self.into).max(moves_bound(board, self.into, self.mode));
+        if bound > limit { return false; }
+        if self.short.get(board).is_some_and(|&short| short >= limit) { return false; }
+
+        for swap in candidate_swaps(board, self.into, self.mode) {
+            if self.within(&board.swap(swap), limit - 1) { return true; }
+        }
+        self.short.insert(board.clone(), limit);
+        return false;
+    }
+}
+
+// Unlike find_swaps, which returns the first solution it comes across, this is guaranteed to give
+// the length of the shortest possible solution. Under the restricted modes it only looks as far as
+// MAX_SWAPS, and returns None for a solution any longer than that.
+pub fn min_swaps(from: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> Option<usize> {
+    return Distances::new(into, mode).get(from);
+}
+
+// A shortest solution, found by following min_swaps' distances down to the target.
+pub fn find_min_swaps(from: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> Option<Vec<Swap>> {
+    let mut distances = Distances::new(into, mode);
+    let mut remaining = distances.get(from)?;
+    let mut cur = from.clone();
+    let mut steps = Vec::new();
+    while remaining > 0 {
+        let swap = candidate_swaps(&cur, into, mode).into_iter()
+            .find(|&swap| distances.within(&cur.swap(swap), remaining - 1))
+            .unwrap();
+        cur = cur.swap(swap);
+        steps.push(swap);
+        remaining -= 1;
+    }
+    return Some(steps);
+}
+
+// Finds every optimal solution. Two solutions which make the same swaps in a different order are
+// considered the same, so only one ordering of each is returned.
+pub fn find_all_swaps(from: &WaffleBoard, into: &WaffleBoard, mode: SwapMode) -> Vec<Vec<Swap>> {
+    fn walk(board: &WaffleBoard, distances: &mut Distances, remaining: usize, path: &mut Vec<Swap>,
+            found: &mut HashMap<Vec<Swap>, Vec<Swap>>) {
+        if remaining == 0 {
+            let mut key = path.clone();
+            key.sort();
+            found.entry(key).or_insert_with(|| path.clone());
+            return;
+        }
+
+        for swap in candidate_swaps(board, distances.into, distances.mode) {
+            // Swaps that touch different cells can be made in either order. Only follow one of
+            // those orders, as the other leads to the same set of swaps.
+            if let Some(&prev) = path.last() {
+                if swap < prev && !swap.overlaps(prev) { continue; }
+            }
+
+            // The next board is at least remaining - 1 swaps away, so this is only false when
+            // the swap doesn't lead along a shortest path.
+            let next = board.swap(swap);
+            if !distances.within(&next, remaining - 1) { continue; }
+
+            path.push(swap);
+            walk(&next, distances, remaining - 1, path, found);
+            path.pop();
+        }
+    }
+
+    let mut distances = Distances::new(into, mode);
+    let remaining = match distances.get(from) {
+        Some(remaining) => remaining,
+        None => return Vec::new(),
+    };
+
+    let mut found = HashMap::new();
+    walk(from, &mut distances, remaining, &mut Vec::new(), &mut found);
+
+    // Sort the solutions, so that the output will be deterministic.
+    return found.into_iter()
+        .sorted()
+        .map(|(_, path)| path)
+        .collect();
+}
+
+// Finds an order to make the given swaps in which turns from into into, or None if there isn't
+// one. Swaps that don't share any cells with each other can be made in any order, so only the
+// order within each group of overlapping swaps needs searching for.
+pub fn order_swaps(set: &[Swap], from: &WaffleBoard, into: &WaffleBoard) -> Option<Vec<Swap>> {
+    // Tries every order of the group's swaps, remembering which partial orders were dead ends.
+    // Two partial orders which made the same swaps and left the same letters behind are the same.
+    fn order(group: &[Swap], board: &WaffleBoard, into: &WaffleBoard, used: &mut Vec<bool>,
+             path: &mut Vec<Swap>, dead: &mut HashSet<(Vec<bool>, WaffleBoard)>) -> bool {
+        if path.len() == group.len() {
+            return group.iter().all(|swap| {
+                board.get(swap.a) == into.get(swap.a) && board.get(swap.b) == into.get(swap.b)
+            });
+        }
+        if dead.contains(&(used.clone(), board.clone())) { return false; }
+
+        for i in 0..group.len() {
+            if used[i] { continue; }
+            used[i] = true;
+            path.push(group[i]);
+            if order(group, &board.swap(group[i]), into, used, path, dead) { return true; }
+            path.pop();
+            used[i] = false;
+        }
+
+        dead.insert((used.clone(), board.clone()));
+        return false;
+    }
+
+    // Group the swaps into connected components, joined by the cells they share.
+    let mut groups: Vec<Vec<Swap>> = Vec::new();
+    for &swap in set.iter().sorted() {
+        let (joined, mut rest): (Vec<Vec<Swap>>, Vec<Vec<Swap>>) = groups.into_iter()
+            .partition(|group| group.iter().any(|other| other.overlaps(swap)));
+        let mut merged: Vec<Swap> = joined.into_iter().flatten().collect();
+        merged.push(swap);
+        rest.push(merged);
+        groups = rest;
+    }
+
+    let mut ret = Vec::new();
+    let mut board = from.clone();
+    for group in groups.iter().sorted() {
+        let mut path = Vec::new();
+        let mut used = vec![false; group.len()];
+        if !order(group, &board, into, &mut used, &mut path, &mut HashSet::new()) { return None; }
+        board = board.apply_all(&path);
+        ret.extend(path);
+    }
+
+    // Cells which none of the swaps touch have to be right already.
+    if board != *into { return None; }
+    return Some(ret);
+}
+
+// Like order_swaps, but out of every order that works, picks the one which completes words
+// soonest: the most complete words after the first swap, then after the second, and so on. Every
+// order is tried, which is fine for the handful of swaps a puzzle takes.
+pub fn order_words_first(set: &[Swap], from: &WaffleBoard, into: &WaffleBoard) -> Option<Vec<Swap>> {
+    // The complete word counts after each remaining swap, and the swaps, for the best way to
+    // finish from board, having made the swaps in used already.
+    type Best = Option<(Vec<usize>, Vec<Swap>)>;
+    fn best(set: &[Swap], board: &WaffleBoard, into: &WaffleBoard, words: &[Vec<Coord>],
+            used: u64, memo: &mut HashMap<(u64, WaffleBoard), Best>) -> Best {
+        if used.count_ones() as usize == set.len() {
+            return (board == into).then(|| (Vec::new(), Vec::new()));
+        }
+        if let Some(known) = memo.get(&(used, board.clone())) { return known.clone(); }
+
+        let mut ret: Best = None;
+        for (i, &swap) in set.iter().enumerate() {
+            if used & (1 << i) != 0 { continue; }
+            let next = board.swap(swap);
+            let rest = best(set, &next, into, words, used | (1 << i), memo);
+            let (mut counts, mut order) = match rest {
+                Some(rest) => rest,
+                None => continue,
+            };
+            let complete = words.iter()
+                .filter(|cells| cells.iter().all(|&coord| next.get(coord) == into.get(coord)))
+                .count();
+            counts.insert(0, complete);
+            order.insert(0, swap);
+            // Ties go to whichever was found first, so the result is deterministic.
+            if ret.as_ref().is_some_and(|(best, _)| *best >= counts) { continue; }
+            ret = Some((counts, order));
+        }
+
+        memo.insert((used, board.clone()), ret.clone());
+        return ret;
+    }
+
+    assert!(set.len() <= 64, "Too many swaps to order");
+    let words = into.word_cells();
+    return best(set, from, into, &words, 0, &mut HashMap::new()).map(|(_, order)| order);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{board, loyal, swaps, Rng};
+
+    #[test]
+    fn one_wrong_cell_has_no_swaps_rather_than_panicking() {
+        let from = loyal();
+        let into = board("
+            loyal
+            i u o
+            locus
+            a c e
+            chaix
+        ");
+        let state = SearchState::new(&from, &into, &SearchOptions::default());
+        assert_eq!(state.get_swaps(&from, &mut Profile::new(false)), Vec::new());
+        let options = SearchOptions::default();
+        assert_eq!(find_swaps(&from, &into, &options, DiffHalf, &mut Profile::new(false)), None);
+    }
+
+    #[test]
+    fn continuing_part_way_through_a_solution_finds_the_rest() {
+        let from = board("
+            coycl
+            l u o
+            laous
+            a c e
+            ihair
+        ");
+        let into = loyal();
+        let options = SearchOptions::default();
+        let full = find_swaps(&from, &into, &options, DiffHalf, &mut Profile::new(false)).unwrap();
+        assert_eq!(full.len(), 4);
+        // The same swaps as the rest of the solution, though ones which don't overlap may come in
+        // either order.
+        let mut rest = continue_solve(&from, &into, &full[..2]).unwrap();
+        let mut expected = full[2..].to_vec();
+        rest.sort();
+        expected.sort();
+        assert_eq!(rest, expected);
+        assert_eq!(continue_solve(&from, &into, &full), Some(Vec::new()));
+    }
+
+    #[test]
+    fn the_trace_counts_wrong_cells_before_and_after_each_swap() {
+        let from = board("
+            coycl
+            l u o
+            laous
+            a c e
+            ihair
+        ");
+        let into = loyal();
+        let (steps, trace) = solve_with_trace(&from, &into).unwrap();
+        assert_eq!(steps.len(), 4);
+        assert_eq!(trace.len(), steps.len() + 1);
+        assert_eq!(trace.first(), Some(&6));
+        assert_eq!(trace.last(), Some(&0));
+        assert!(trace.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", trace);
+
+        assert_eq!(solve_with_trace(&into, &into), Some((Vec::new(), vec![0])));
+    }
+
+    #[test]
+    fn minimizing_drops_the_swaps_a_solution_can_do_without() {
+        let from = board("
+            coycl
+            l u o
+            laous
+            a c e
+            ihair
+        ");
+        let into = loyal();
+        let shortest = find_min_swaps(&from, &into, SwapMode::Any).unwrap();
+        assert_eq!(shortest.len(), 4);
+        // A swap made twice over undoes itself, whichever way round it's written.
+        let mut padded = swaps("0,0-4,4 0,0-4,4");
+        padded.extend(&shortest[..2]);
+        padded.extend(swaps("2,0-2,4 2,4-2,0"));
+        padded.extend(&shortest[2..]);
+        assert_eq!(from.apply_all(&padded), into);
+        assert_eq!(minimize(&from, &into, &padded), shortest);
+
+        // Steps which don't solve the puzzle are left alone.
+        assert_eq!(minimize(&from, &into, &padded[..4]), padded[..4]);
+    }
+
+    #[test]
+    fn continuing_from_a_gap_swap_gives_none() {
+        let into = loyal();
+        assert_eq!(continue_solve(&into, &into, &swaps("1,1-0,0")), None);
+    }
+
+    fn search_options(mode: SwapMode) -> SearchOptions {
+        return SearchOptions { swap_mode: mode, ..SearchOptions::default() };
+    }
+
+    #[test]
+    fn restricted_modes_take_detours_through_correct_cells() {
+        // The c and the l share neither a row nor a column, so under line mode they have to go by
+        // way of a cell which is already right.
+        let from = board("
+            coyal
+            i u o
+            lolus
+            a c e
+            chair
+        ");
+        let into = loyal();
+        let options = search_options(SwapMode::Line);
+        let steps = find_swaps(&from, &into, &options, DiffHalf, &mut Profile::new(false)).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(steps.iter().all(|&swap| SwapMode::Line.allows(swap)));
+        assert_eq!(from.apply_all(&steps), into);
+        assert_eq!(min_swaps(&from, &into, SwapMode::Line), Some(2));
+        assert_eq!(find_min_swaps(&from, &into, SwapMode::Line).map(|steps| steps.len()), Some(2));
+
+        // Under row mode, the letters can never change rows.
+        let options = search_options(SwapMode::Row);
+        assert_eq!(find_swaps(&from, &into, &options, DiffHalf, &mut Profile::new(false)), None);
+        assert_eq!(min_swaps(&from, &into, SwapMode::Row), None);
+        assert!(!can_reach(&from, &into, SwapMode::Row));
+        assert_eq!(min_swaps(&from, &into, SwapMode::Any), Some(1));
+    }
+
+    #[test]
+    fn row_mode_only_swaps_within_rows() {
+        let from = board("
+            oylal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        let into = loyal();
+        let options = search_options(SwapMode::Row);
+        let steps = find_swaps(&from, &into, &options, DiffHalf, &mut Profile::new(false)).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(steps.iter().all(|swap| swap.a.row == swap.b.row));
+        assert_eq!(from.apply_all(&steps), into);
+    }
+
+    #[test]
+    fn every_optimal_solution_is_found_once() {
+        let from = board("
+            oylal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        let into = loyal();
+        assert_eq!(min_swaps(&from, &into, SwapMode::Any), Some(2));
+        let all = find_all_swaps(&from, &into, SwapMode::Any);
+        assert_eq!(all, vec![swaps("0,0-0,1 0,0-0,2"), swaps("0,1-0,2 0,0-0,1"),
+                             swaps("0,0-0,2 0,1-0,2")]);
+        assert!(all.iter().all(|steps| from.apply_all(steps) == into));
+    }
+
+    #[test]
+    fn canonicalizing_explores_fewer_boards() {
+        // The first and last rows of the target are the same, and so are its outer columns. The
+        // start mirrors itself across those rows, so many of the boards on the way come in pairs.
+        let from = board("
+            babad
+            c e d
+            fghgf
+            i j i
+            babac
+        ");
+        let into = board("
+            abcba
+            d e d
+            fghgf
+            i j i
+            abcba
+        ");
+        // Under CycleBound the search heads so straight for the target that there's little left
+        // to merge, so this explores more widely with DiffHalf.
+        let explore = |canonicalize| {
+            let options = SearchOptions { canonicalize: canonicalize, ..SearchOptions::default() };
+            let mut state = SearchState::with_heuristic(&from, &into, &options, DiffHalf);
+            let steps = state.run(&mut Profile::new(false), &mut |_| {}).unwrap();
+            return (steps, state.stats().explored);
+        };
+        let (plain, plain_explored) = explore(false);
+        let (merged, merged_explored) = explore(true);
+        assert_eq!(from.apply_all(&merged), into);
+        assert_eq!(merged.len(), plain.len());
+        assert_eq!(merged.len(), 7);
+        assert!(merged_explored < plain_explored, "{} >= {}", merged_explored, plain_explored);
+    }
+
+    #[test]
+    fn fewer_wrong_cells_sort_first_and_both_solvers_agree() {
+        let into = loyal();
+        let near = into.swap(Swap::new(Coord{ row: 0, col: 0 }, Coord{ row: 2, col: 2 }));
+        let far = board("
+            coycl
+            l u o
+            laous
+            a c e
+            ihair
+        ");
+        assert_eq!(near.hamming_distance(&into), 2);
+        assert_eq!(far.hamming_distance(&into), 6);
+        assert_eq!(far.score(&into), far.hamming_distance(&into));
+
+        // Both cost three swaps, but the board with fewer cells left wrong is explored first.
+        let near_state = State::new(near.clone(), 2, DiffHalf.estimate(&near, &into));
+        let far_state = State::new(far.clone(), 0, DiffHalf.estimate(&far, &into));
+        assert_eq!(near_state.cost, far_state.cost);
+        assert!(near_state < far_state);
+
+        let options = SearchOptions::default();
+        let found = find_swaps(&far, &into, &options, DiffHalf, &mut Profile::new(false)).unwrap();
+        let shortest = find_min_swaps(&far, &into, SwapMode::Any).unwrap();
+        assert_eq!(found.len(), shortest.len());
+        assert_eq!(far.apply_all(&shortest), into);
+    }
+
+    #[test]
+    fn stats_cover_everything_the_search_remembers() {
+        let from = board("
+            coycl
+            l u o
+            laous
+            a c e
+            ihair
+        ");
+        let into = loyal();
+        let mut state = SearchState::new(&from, &into, &SearchOptions::default());
+        let steps = state.run(&mut Profile::new(false), &mut |_| {}).unwrap();
+        assert_eq!(steps.len(), 4);
+        let stats = state.stats().clone();
+        assert!(stats.explored > 0);
+        assert!(stats.peak_frontier > 0);
+        // The board which reaches the target adds nothing more to remember.
+        assert!(stats.peak_visited >= state.map.len(), "{} < {}", stats.peak_visited,
+                state.map.len());
+        assert!(stats.peak_visited >= stats.explored);
+    }
+
+    #[test]
+    fn hashed_and_exact_keys_find_paths_of_the_same_length() {
+        let from = board("
+            lauol
+            s y l
+            cucoh
+            e i i
+            coaar
+        ");
+        let into = loyal();
+        let solve = |exact_keys| {
+            let options = SearchOptions { exact_keys: exact_keys, ..SearchOptions::default() };
+            let mut state = SearchState::with_heuristic(&from, &into, &options, CycleBound);
+            let steps = state.run(&mut Profile::new(false), &mut |_| {}).unwrap();
+            return (steps, state.stats().clone());
+        };
+        let (hashed, hashed_stats) = solve(false);
+        let (exact, exact_stats) = solve(true);
+        assert_eq!(hashed.len(), 10);
+        assert_eq!(exact.len(), hashed.len());
+        assert_eq!(from.apply_all(&exact), into);
+        // Without a collision, the two remember exactly the same boards.
+        assert_eq!(exact_stats, hashed_stats);
+    }
+
+    // Four swaps for every wrong cell, which overestimates so much that the search rushes at
+    // whichever board has the fewest wrong cells.
+    struct EveryCell;
+
+    impl Heuristic for EveryCell {
+        fn estimate(&self, cur: &WaffleBoard, target: &WaffleBoard) -> usize {
+            return cur.hamming_distance(target) * 4;
+        }
+    }
+
+    #[test]
+    fn admissible_heuristics_find_shortest_paths_and_others_may_not() {
+        // Always taking a swap which fixes two cells, as EveryCell has the search do, costs a swap.
+        let from = board("
+            coycl
+            l u o
+            laous
+            a c e
+            ihair
+        ");
+        let into = loyal();
+        let options = SearchOptions::default();
+        let mut profile = Profile::new(false);
+        let diff_half = find_swaps(&from, &into, &options, DiffHalf, &mut profile).unwrap();
+        let cycle_bound = find_swaps(&from, &into, &options, CycleBound, &mut profile).unwrap();
+        let every_cell = find_swaps(&from, &into, &options, EveryCell, &mut profile).unwrap();
+        assert_eq!(min_swaps(&from, &into, SwapMode::Any), Some(4));
+        assert_eq!(diff_half.len(), 4);
+        assert_eq!(cycle_bound.len(), 4);
+        assert_eq!(every_cell.len(), 5);
+        for steps in [diff_half, cycle_bound, every_cell] {
+            assert_eq!(from.apply_all(&steps), into);
+        }
+    }
+
+    #[test]
+    fn greedy_shortcut_skips_the_search_only_when_it_is_provably_optimal() {
+        let into = loyal();
+        let options = SearchOptions::default();
+        // Two pairs of swapped letters, which greedy fixes two at a time.
+        let easy = board("
+            lyoal
+            i u o
+            locus
+            a c e
+            chiar
+        ");
+        let mut profile = Profile::new(true);
+        let steps = find_swaps(&easy, &into, &options, CycleBound, &mut profile).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(greedy_shortcut(&easy, &into, SwapMode::Any), Some(steps));
+        assert!(!profile.report().contains("frontier"), "{}", profile.report());
+
+        let hard = board("
+            lauol
+            s y l
+            cucoh
+            e i i
+            coaar
+        ");
+        let mut profile = Profile::new(true);
+        let steps = find_swaps(&hard, &into, &options, CycleBound, &mut profile).unwrap();
+        assert_eq!(steps.len(), 10);
+        assert_eq!(hard.apply_all(&steps), into);
+        assert_eq!(greedy_shortcut(&hard, &into, SwapMode::Any), None);
+        assert!(profile.report().contains("frontier"), "{}", profile.report());
+    }
+
+    #[test]
+    fn order_swaps_finds_the_order_a_cycle_needs() {
+        let from = board("
+            oylal
+            i u o
+            locus
+            a c e
+            chiar
+        ");
+        let into = loyal();
+        // The cycle in the first row only comes right one way round.
+        let set = swaps("0,0-0,2 4,2-4,3 0,0-0,1");
+        assert_ne!(from.apply_all(&set), into);
+        let ordered = order_swaps(&set, &from, &into).unwrap();
+        assert_eq!(from.apply_all(&ordered), into);
+        assert_eq!(ordered, swaps("0,0-0,1 0,0-0,2 4,2-4,3"));
+
+        // Swaps which don't solve the board in any order.
+        assert_eq!(order_swaps(&swaps("0,0-0,1 4,2-4,3"), &from, &into), None);
+        assert_eq!(order_swaps(&swaps("0,0-0,1 0,1-0,2 0,0-0,2 4,2-4,3"), &from, &into), None);
+    }
+
+    #[test]
+    fn diagnosis_names_the_letters_which_differ() {
+        let from = board("
+            zoyal
+            i u o
+            locus
+            a c z
+            chair
+        ");
+        let into = loyal();
+        let outcome = solve(&from, &into, &SearchOptions::default(), &mut Profile::new(false));
+        assert_eq!(outcome, SolveOutcome::LettersDiffer);
+        assert_eq!(diagnose(&from, &into, &outcome), "\
+Unsolvable: letter sets differ
+deficit 'e': 1 too few
+deficit 'l': 1 too few
+surplus 'z': 2 too many");
+    }
+
+    #[test]
+    fn an_interrupted_search_reports_how_close_it_got() {
+        let from = board("
+            lauol
+            s y l
+            cucoh
+            e i i
+            coaar
+        ");
+        let into = loyal();
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut state = SearchState::with_heuristic(&from, &into, &SearchOptions::default(),
+                                                    CycleBound);
+        state.interrupt_on(flag.clone());
+        let mut seen = 0;
+        let failure = state.run(&mut Profile::new(false), &mut |_| {
+            seen += 1;
+            if seen > 5 { flag.store(true, Ordering::Relaxed); }
+        }).unwrap_err();
+        assert!(failure.interrupted);
+        assert!(failure.closest_path.len() > 0);
+        assert_eq!(from.apply_all(&failure.closest_path), failure.closest);
+        assert!(failure.closest.score(&into) < from.score(&into));
+        assert!(failure.closest.score(&into) > 0);
+        assert!(state.stats().peak_visited >= state.map.len());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn a_search_resumed_from_a_checkpoint_finds_the_same_path() {
+        use crate::fixtures::TempPath;
+
+        let from = board("
+            lauol
+            s y l
+            cucoh
+            e i i
+            coaar
+        ");
+        let into = loyal();
+        let options = SearchOptions::default();
+        let mut profile = Profile::new(false);
+        let mut state = SearchState::with_heuristic(&from, &into, &options, CycleBound);
+        let uninterrupted = state.run(&mut profile, &mut |_| ()).unwrap();
+        assert_eq!(uninterrupted.len(), 10);
+        let explored = state.stats().explored;
+
+        let checkpoint = TempPath::new("checkpoint.json");
+        let path = checkpoint.path();
+        for stop_after in [1, explored / 3, explored * 2 / 3] {
+            let flag = Arc::new(AtomicBool::new(false));
+            let mut state = SearchState::with_heuristic(&from, &into, &options, CycleBound);
+            state.interrupt_on(flag.clone());
+            let mut seen = 0;
+            let result = state.run(&mut profile, &mut |_| {
+                seen += 1;
+                if seen > stop_after { flag.store(true, Ordering::Relaxed); }
+            });
+            assert!(result.is_err_and(|failure| failure.interrupted));
+            state.save(path).unwrap();
+
+            let mut resumed = SearchState::load(path, CycleBound).unwrap();
+            assert!(resumed.is_for(&from, &into, &options));
+            assert_eq!(resumed.run(&mut profile, &mut |_| ()), Ok(uninterrupted.clone()));
+        }
+    }
+
+    #[test]
+    fn solutions_never_swap_a_gap() {
+        let from = board("
+            lauol
+            s y l
+            cucoh
+            e i i
+            coaar
+        ");
+        let into = loyal();
+        let options = SearchOptions::default();
+        let steps = find_swaps(&from, &into, &options, CycleBound, &mut Profile::new(false));
+        assert!(steps.unwrap().iter().all(|swap| !swap.touches_gap(into.layout())));
+
+        let cycle = board("
+            oylal
+            i u o
+            locus
+            a c e
+            chair
+        ");
+        for mode in [SwapMode::Any, SwapMode::Line, SwapMode::Row] {
+            let solutions = find_all_swaps(&cycle, &into, mode);
+            assert!(solutions.len() > 0);
+            assert!(solutions.iter().flatten().all(|swap| !swap.touches_gap(into.layout())));
+        }
+    }
+
+    #[test]
+    fn words_first_completes_a_word_sooner() {
+        let from = board("
+            lauol
+            s y l
+            cucoh
+            e i i
+            coaar
+        ");
+        let into = loyal();
+        // How many words are right after each swap.
+        let complete = |steps: &[Swap]| -> Vec<usize> {
+            let mut board = from.clone();
+            return steps.iter()
+                .map(|&swap| {
+                    board = board.swap(swap);
+                    into.word_cells().iter()
+                        .filter(|cells| cells.iter().all(|&cell| board.get(cell) == into.get(cell)))
+                        .count()
+                })
+                .collect();
+        };
+        let options = SearchOptions::default();
+        let naive = find_swaps(&from, &into, &options, CycleBound, &mut Profile::new(false))
+            .unwrap();
+        let ordered = order_words_first(&naive, &from, &into).unwrap();
+        assert_eq!(from.apply_all(&ordered), into);
+        // The search's own order only completes a word on the fifth swap.
+        assert_eq!(complete(&naive), [0, 0, 0, 0, 1, 1, 2, 2, 3, 6]);
+        assert_eq!(complete(&ordered), [0, 1, 1, 1, 2, 2, 2, 3, 5, 6]);
+    }
+
+    // examples/replay.rs over a few seeded rounds: the swaps found replay into the target, and no
+    // shorter way there exists.
+    #[test]
+    fn seeded_scrambles_are_solved_in_the_fewest_swaps() {
+        let into = loyal();
+        let cells = into.active_coords().to_vec();
+        let mut rng = Rng::new(0x5eed);
+
+        for round in 0..20 {
+            let mut from = into.clone();
+            for _ in 0..rng.below(6) {
+                let a = cells[rng.below(cells.len())];
+                let b = cells[rng.below(cells.len())];
+                if a == b { continue; }
+                from = from.swap(Swap::new(a, b));
+            }
+
+            let options = SearchOptions::default();
+            let steps = find_swaps(&from, &into, &options, DiffHalf, &mut Profile::new(false))
+                .unwrap_or_else(|| panic!("round {}: no path found", round));
+            assert_eq!(from.apply_all(&steps), into, "round {}", round);
+            assert_eq!(min_swaps(&from, &into, options.swap_mode), Some(steps.len()), "round {}", round);
+        }
+    }
+}
@@ -0,0 +1,667 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+use std::path::Path;
+use std::{env, fs, process};
+
+use waffler::auto::{auto_solve, best_reachable, Prefer};
+use waffler::board::{board_lines, CoordStyle, Swap, WaffleBoard};
+use waffler::csv;
+use waffler::dictionary::Dictionary;
+use waffler::frames::{Frame, Frames};
+use waffler::profile::Profile;
+use waffler::search::{min_swaps, solve, SearchOptions, SolveOutcome};
+
+fn load_board(path: &Path) -> WaffleBoard {
+    return WaffleBoard::new(path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path.display(), err);
+        process::exit(1);
+    });
+}
+
+fn load_dictionary(path: &Path) -> Dictionary {
+    let dict = Dictionary::from_file(path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path.display(), err);
+        process::exit(1);
+    });
+    if dict.is_empty() {
+        eprintln!("Error: word list is empty after filtering");
+        process::exit(1);
+    }
+    return dict;
+}
+
+// Splits the flags out of a command's arguments, returning the choice of --prefer along with the
+// rest of the arguments.
+fn parse_prefer(args: &[String]) -> (Prefer, Vec<String>) {
+    let mut prefer = Prefer::default();
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--prefer" => {
+                let name = args.next().unwrap_or_else(|| {
+                    eprintln!("Expected a value after {}", arg);
+                    process::exit(1);
+                });
+                prefer = Prefer::parse(name).unwrap_or_else(|| {
+                    eprintln!("Unknown preference: {} (expected likelihood or swaps)", name);
+                    process::exit(1);
+                });
+            },
+            flag if flag.starts_with("--") => {
+                eprintln!("Unknown flag: {}", flag);
+                process::exit(1);
+            },
+            _ => rest.push(arg.clone()),
+        }
+    }
+    return (prefer, rest);
+}
+
+// waffle auto [--prefer likelihood|swaps] <wordlist> <letters> <colors>
+fn auto(args: &[String]) {
+    const USAGE: &str =
+        "Usage: waffle auto [--prefer likelihood|swaps] <wordlist> <letters> <colors>";
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{}", USAGE);
+        println!();
+        println!("<colors> is a grid the same shape as <letters>, with a 'g' for each green tile.");
+        println!("Only the greens are used to work out the answer: yellow and grey tiles may be");
+        println!("written as anything else, and the hints they give are ignored.");
+        return;
+    }
+    let (prefer, args) = parse_prefer(args);
+    if args.len() != 3 {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+
+    let dict = load_dictionary(Path::new(&args[0]));
+    let letters = load_board(Path::new(&args[1]));
+    let colors = load_board(Path::new(&args[2]));
+    if letters.size() != colors.size() {
+        eprintln!("The letters and colors must be the same size");
+        process::exit(1);
+    }
+
+    let found = auto_solve(&letters, &colors, &dict, prefer).unwrap_or_else(|err| {
+        eprintln!("{}: {}", args[1], err);
+        process::exit(1);
+    });
+    show_solution(&letters, found);
+}
+
+// waffle best [--prefer likelihood|swaps] <wordlist> <letters>
+fn best(args: &[String]) {
+    let (prefer, args) = parse_prefer(args);
+    if args.len() != 2 {
+        eprintln!("Usage: waffle best [--prefer likelihood|swaps] <wordlist> <letters>");
+        process::exit(1);
+    }
+
+    let dict = load_dictionary(Path::new(&args[0]));
+    let letters = load_board(Path::new(&args[1]));
+
+    let found = best_reachable(&letters, &dict, prefer).unwrap_or_else(|err| {
+        eprintln!("{}: {}", args[1], err);
+        process::exit(1);
+    });
+    show_solution(&letters, found);
+}
+
+// waffle edit [--size N] [<file>]
+//
+// Edits the board in file, or a new blank one if there's no such file, then saves it back there.
+// Without a file, the board is printed instead. The editor is drawn on stderr, out of the way.
+#[cfg(feature = "edit")]
+fn edit(args: &[String]) {
+    use std::io::{self, Write};
+
+    use crossterm::{cursor, event, execute, queue, terminal};
+    use waffler::board::WaffleLayout;
+    use waffler::editor::{Action, Editor};
+
+    let mut layout = WaffleLayout::standard_5x5();
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => {
+                let value = args.next().map(|value| value.parse::<usize>());
+                layout = match value {
+                    Some(Ok(size)) if size % 2 == 1 => WaffleLayout::new(size, size),
+                    _ => {
+                        eprintln!("Expected an odd number after --size");
+                        process::exit(1);
+                    },
+                };
+            },
+            flag if flag.starts_with("--") => {
+                eprintln!("Unknown flag: {}", flag);
+                process::exit(1);
+            },
+            _ => path = Some(Path::new(arg)),
+        }
+    }
+
+    let mut editor = match path {
+        Some(path) if path.exists() => Editor::from_board(&load_board(path)),
+        _ => Editor::new(layout),
+    };
+
+    let run = |editor: &mut Editor| -> io::Result<Action> {
+        let mut out = io::stderr();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen)?;
+        let action = loop {
+            queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+            // Raw mode doesn't return to the start of the line on a newline.
+            write!(out, "{}\r\n\r\nEnter to finish, Escape to cancel",
+                   editor.text().replace('\n', "\r\n"))?;
+            queue!(out, cursor::MoveTo(editor.cursor.col as u16, editor.cursor.row as u16))?;
+            out.flush()?;
+            if let event::Event::Key(key) = event::read()? {
+                match editor.handle(key) {
+                    Action::Continue => continue,
+                    action => break action,
+                }
+            }
+        };
+        execute!(out, terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        return Ok(action);
+    };
+
+    let action = run(&mut editor).unwrap_or_else(|err| {
+        let _ = crossterm::terminal::disable_raw_mode();
+        eprintln!("Terminal error: {}", err);
+        process::exit(1);
+    });
+    if action == Action::Cancel { process::exit(1); }
+
+    let text = editor.text() + "\n";
+    match path {
+        Some(path) => std::fs::write(path, text).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path.display(), err);
+            process::exit(1);
+        }),
+        None => print!("{}", text),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum BatchFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+impl BatchFormat {
+    fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "table" => Some(Self::Table),
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        };
+    }
+}
+
+// How one puzzle in a batch went: the number of swaps, or None if there's no way to solve it.
+struct BatchResult {
+    name: String,
+    swaps: Option<usize>,
+}
+
+// A puzzle file holds the scrambled board, then a blank line (which may hold stray whitespace),
+// then the solved board.
+fn load_pair(path: &Path) -> Result<(WaffleBoard, WaffleBoard), String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let lines: Vec<&str> = board_lines(&text).collect();
+    let split = lines.iter().position(|line| line.trim().is_empty())
+        .ok_or("expected a blank line between the two boards")?;
+    let from: WaffleBoard = lines[..split].join("\n").parse().map_err(|err| format!("{}", err))?;
+    let into: WaffleBoard = lines[split + 1..].iter()
+        .skip_while(|line| line.trim().is_empty())
+        .take_while(|line| !line.trim().is_empty())
+        .copied()
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .parse()
+        .map_err(|err| format!("{}", err))?;
+    from.check_same_layout(&into).map_err(|err| err.to_string())?;
+    return Ok((from, into));
+}
+
+fn json_string(text: &str) -> String {
+    return format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+// With flag_over, each puzzle also says whether it took more swaps than that, which a well formed
+// one never should. Puzzles which can't be solved at all are always flagged.
+fn show_batch(results: &[BatchResult], format: BatchFormat, flag_over: Option<usize>) {
+    let swaps = |result: &BatchResult| {
+        return result.swaps.map_or(String::new(), |swaps| swaps.to_string());
+    };
+    let flagged = |result: &BatchResult| {
+        return flag_over.is_some_and(|over| result.swaps.is_none_or(|swaps| swaps > over));
+    };
+    match format {
+        BatchFormat::Table => {
+            let width = results.iter().map(|result| result.name.chars().count()).max().unwrap_or(0);
+            let width = width.max("file".len());
+            match flag_over {
+                Some(_) => println!("{:<width$}  {:>5}  solvable  flagged", "file", "swaps",
+                                    width = width),
+                None => println!("{:<width$}  {:>5}  solvable", "file", "swaps", width = width),
+            }
+            for result in results {
+                let solvable = if result.swaps.is_some() { "yes" } else { "no" };
+                match flag_over {
+                    Some(_) => println!("{:<width$}  {:>5}  {:<8}  {}", result.name, swaps(result),
+                                        solvable, if flagged(result) { "yes" } else { "no" },
+                                        width = width),
+                    None => println!("{:<width$}  {:>5}  {}", result.name, swaps(result), solvable,
+                                     width = width),
+                }
+            }
+        },
+        BatchFormat::Csv => {
+            println!("file,swaps,solvable{}", if flag_over.is_some() { ",flagged" } else { "" });
+            for result in results {
+                let solvable = result.swaps.is_some();
+                let flag = flag_over.map_or(String::new(), |_| format!(",{}", flagged(result)));
+                println!("{},{},{}{}", csv::field(&result.name), swaps(result), solvable, flag);
+            }
+        },
+        BatchFormat::Json => {
+            let rows: Vec<String> = results.iter().map(|result| {
+                let swaps = result.swaps.map_or("null".to_owned(), |swaps| swaps.to_string());
+                let flag = flag_over.map_or(String::new(), |_| {
+                    return format!(",\"flagged\":{}", flagged(result));
+                });
+                return format!("{{\"file\":{},\"swaps\":{},\"solvable\":{}{}}}",
+                               json_string(&result.name), swaps, result.swaps.is_some(), flag);
+            }).collect();
+            println!("[{}]", rows.join(","));
+        },
+    }
+}
+
+// waffle batch [--format table|json|csv] [--flag-over N] <dir>
+//
+// Solves every *.txt puzzle file in dir and prints a line for each. Files which can't be read are
+// skipped and listed at the end, rather than stopping the rest of the batch.
+fn batch(args: &[String]) {
+    let mut format = BatchFormat::default();
+    let mut flag_over = None;
+    let mut dirs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--flag-over" => {
+                let value = args.next().unwrap_or_else(|| {
+                    eprintln!("Expected a value after {}", arg);
+                    process::exit(1);
+                });
+                flag_over = Some(value.parse::<usize>().unwrap_or_else(|_| {
+                    eprintln!("Invalid swap count: {}", value);
+                    process::exit(1);
+                }));
+            },
+            "--format" => {
+                let name = args.next().unwrap_or_else(|| {
+                    eprintln!("Expected a value after {}", arg);
+                    process::exit(1);
+                });
+                format = BatchFormat::parse(name).unwrap_or_else(|| {
+                    eprintln!("Unknown format: {} (expected table, json, or csv)", name);
+                    process::exit(1);
+                });
+            },
+            flag if flag.starts_with("--") => {
+                eprintln!("Unknown flag: {}", flag);
+                process::exit(1);
+            },
+            _ => dirs.push(Path::new(arg)),
+        }
+    }
+    if dirs.len() != 1 {
+        eprintln!("Usage: waffle batch [--format table|json|csv] [--flag-over N] <dir>");
+        process::exit(1);
+    }
+
+    let dir = dirs[0];
+    let entries = fs::read_dir(dir).unwrap_or_else(|err| {
+        eprintln!("{}: {}", dir.display(), err);
+        process::exit(1);
+    });
+    let mut paths: Vec<_> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    let mut profile = Profile::new(false);
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let (from, into) = match load_pair(&path) {
+            Ok(pair) => pair,
+            Err(err) => {
+                errors.push(format!("{}: {}", name, err));
+                continue;
+            },
+        };
+        let options = SearchOptions::default();
+        let swaps = match solve(&from, &into, &options, &mut profile) {
+            SolveOutcome::Solved(steps) => Some(steps.len()),
+            // The search gives up on paths longer than MAX_SWAPS, but those are just the puzzles
+            // --flag-over is for, so count them without a limit.
+            SolveOutcome::NotFound(_) => min_swaps(&from, &into, options.swap_mode),
+            _ => None,
+        };
+        results.push(BatchResult { name: name, swaps: swaps });
+    }
+
+    show_batch(&results, format, flag_over);
+    if errors.len() > 0 {
+        eprintln!("Could not read {} of the puzzle files:", errors.len());
+        errors.iter().for_each(|err| eprintln!("  {}", err));
+        process::exit(1);
+    }
+}
+
+// The frame as text: which swap led to it, then the board.
+fn frame_text(frame: &Frame, total: usize) -> String {
+    let swap = frame.swap.map_or("start".to_owned(), |swap| swap.to_string());
+    return format!("step {} of {}: {}\n{}", frame.step, total, swap, frame.board.display());
+}
+
+// Draws each frame in place, with the cells already right in green, waiting delay between them.
+// Any key skips ahead, and Escape or q stops.
+#[cfg(feature = "watch")]
+fn animate(frames: Frames, total: usize, delay: std::time::Duration) -> std::io::Result<()> {
+    use std::io::{self, Write};
+
+    use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor};
+    use crossterm::{cursor, event, execute, queue, terminal};
+    use waffler::board::{CellStatus, Coord};
+
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = (|| -> io::Result<()> {
+        for frame in frames {
+            queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+            let swap = frame.swap.map_or("start".to_owned(), |swap| swap.to_string());
+            queue!(out, Print(format!("step {} of {}: {}\r\n\r\n", frame.step, total, swap)))?;
+            for (row, statuses) in frame.status.iter().enumerate() {
+                for (col, status) in statuses.iter().enumerate() {
+                    let coord = Coord{ row: row, col: col };
+                    let color = match status {
+                        CellStatus::Correct => Color::DarkGreen,
+                        CellStatus::Wrong => Color::DarkGrey,
+                        CellStatus::Gap => Color::Reset,
+                    };
+                    let moved = frame.swap.is_some_and(|swap| swap.a == coord || swap.b == coord);
+                    if moved { queue!(out, SetAttribute(Attribute::Bold))?; }
+                    queue!(out, SetBackgroundColor(color),
+                           Print(format!(" {} ", frame.board.get(coord).to_ascii_uppercase())),
+                           ResetColor, SetAttribute(Attribute::Reset))?;
+                }
+                queue!(out, Print("\r\n"))?;
+            }
+            // The last frame stays up until a key is pressed, rather than vanishing at once.
+            let last = frame.step == total;
+            if last { queue!(out, Print("\r\nPress any key to finish"))?; }
+            out.flush()?;
+
+            if last || event::poll(delay)? {
+                if let event::Event::Key(key) = event::read()? {
+                    let quit = [event::KeyCode::Esc, event::KeyCode::Char('q')];
+                    if quit.contains(&key.code) { return Ok(()); }
+                }
+            }
+        }
+        return Ok(());
+    })();
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    return result;
+}
+
+// waffle watch [--delay MS] [--headless] <from> <into>
+//
+// Solves the puzzle, then plays the solution back a swap at a time. Without the watch feature, or
+// with --headless, or when the output isn't a terminal, each frame is just printed in turn.
+fn watch(args: &[String]) {
+    let mut delay = 1000;
+    let mut headless = false;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => headless = true,
+            "--delay" => {
+                delay = match args.next().map(|value| value.parse::<u64>()) {
+                    Some(Ok(delay)) => delay,
+                    _ => {
+                        eprintln!("Expected a number of milliseconds after --delay");
+                        process::exit(1);
+                    },
+                };
+            },
+            flag if flag.starts_with("--") => {
+                eprintln!("Unknown flag: {}", flag);
+                process::exit(1);
+            },
+            _ => paths.push(Path::new(arg)),
+        }
+    }
+    if paths.len() != 2 {
+        eprintln!("Usage: waffle watch [--delay MS] [--headless] <from> <into>");
+        process::exit(1);
+    }
+
+    let from = load_board(paths[0]);
+    let into = load_board(paths[1]);
+    if let Err(err) = from.check_same_layout(&into) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+    let steps = match solve(&from, &into, &SearchOptions::default(), &mut Profile::new(false)) {
+        SolveOutcome::Solved(steps) => steps,
+        outcome => {
+            eprintln!("{}", outcome);
+            process::exit(1);
+        },
+    };
+
+    let frames = Frames::new(&from, &into, &steps);
+    #[cfg(feature = "watch")]
+    if !headless && std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+        let delay = std::time::Duration::from_millis(delay);
+        animate(frames, steps.len(), delay).unwrap_or_else(|err| {
+            let _ = crossterm::terminal::disable_raw_mode();
+            eprintln!("Terminal error: {}", err);
+            process::exit(1);
+        });
+        return;
+    }
+    // Only the animation has any use for these.
+    #[cfg(not(feature = "watch"))]
+    let _ = (headless, delay);
+    let text: Vec<String> = frames.map(|frame| frame_text(&frame, steps.len())).collect();
+    println!("{}", text.join("\n\n"));
+}
+
+// A request to waffle http: either the two boards, or the scramble and its colours along with
+// the words the answer might use. Boards are written as text, with a newline after each row.
+#[cfg(feature = "http")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum SolveRequest {
+    Boards { from: String, into: String },
+    Auto { letters: String, colors: String, dictionary: Option<Vec<String>> },
+}
+
+// Answers a request to waffle http with a status and a JSON body: the solution, or what was wrong.
+#[cfg(feature = "http")]
+fn answer(body: &str, dict: Option<&Dictionary>,
+          store: &mut waffler::cache::MemoryStore) -> (u16, String) {
+    use serde_json::json;
+    use waffler::cache::find_swaps_cached;
+
+    let error = |status: u16, message: String| (status, json!({ "error": message }).to_string());
+    let parse = |name: &str, text: &str| -> Result<WaffleBoard, (u16, String)> {
+        return text.parse().map_err(|err| error(400, format!("{}: {}", name, err)));
+    };
+    let result = (|| -> Result<serde_json::Value, (u16, String)> {
+        let expected = "Expected {from, into} or {letters, colors, dictionary}";
+        let request: SolveRequest = serde_json::from_str(body)
+            .map_err(|_| error(400, expected.to_owned()))?;
+        return match request {
+            SolveRequest::Boards { from, into } => {
+                let from = parse("from", &from)?;
+                let into = parse("into", &into)?;
+                from.check_same_layout(&into).map_err(|err| error(400, err.to_string()))?;
+                if from.letter_counts() != into.letter_counts() {
+                    return Err(error(422, SolveOutcome::LettersDiffer.to_string()));
+                }
+                let steps = find_swaps_cached(&from, &into, store)
+                    .ok_or_else(|| error(422, "No path found".to_owned()))?;
+                Ok(json!({ "swaps": steps }))
+            },
+            SolveRequest::Auto { letters, colors, dictionary } => {
+                let letters = parse("letters", &letters)?;
+                let colors = parse("colors", &colors)?;
+                letters.check_same_layout(&colors).map_err(|err| error(400, err.to_string()))?;
+                let given = dictionary.map(Dictionary::new);
+                let dict = given.as_ref().or(dict)
+                    .ok_or_else(|| error(400, "Expected a dictionary".to_owned()))?;
+                let (solved, steps) = auto_solve(&letters, &colors, dict, Prefer::default())
+                    .map_err(|err| error(400, format!("letters: {}", err)))?
+                    .ok_or_else(|| error(422, "Could not find a solution".to_owned()))?;
+                Ok(json!({ "solved": solved.display(), "swaps": steps }))
+            },
+        };
+    })();
+    return match result {
+        Ok(value) => (200, value.to_string()),
+        Err(err) => err,
+    };
+}
+
+// waffle http [--port N] [--wordlist <file>]
+//
+// Serves answer on localhost, to POST requests to any path. The word list is used for requests
+// which don't bring their own dictionary. Solutions are cached for as long as the server runs.
+#[cfg(feature = "http")]
+fn http(args: &[String]) {
+    use tiny_http::{Header, Method, Response, Server};
+
+    let mut port: u16 = 8080;
+    let mut dict = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                port = match args.next().map(|value| value.parse()) {
+                    Some(Ok(port)) => port,
+                    _ => {
+                        eprintln!("Expected a port number after --port");
+                        process::exit(1);
+                    },
+                };
+            },
+            "--wordlist" => {
+                let path = args.next().unwrap_or_else(|| {
+                    eprintln!("Expected a value after {}", arg);
+                    process::exit(1);
+                });
+                dict = Some(load_dictionary(Path::new(path)));
+            },
+            _ => {
+                eprintln!("Usage: waffle http [--port N] [--wordlist <file>]");
+                process::exit(1);
+            },
+        }
+    }
+
+    let server = Server::http(("127.0.0.1", port)).unwrap_or_else(|err| {
+        eprintln!("Could not listen on port {}: {}", port, err);
+        process::exit(1);
+    });
+    eprintln!("Listening on http://127.0.0.1:{}", port);
+
+    let mut store = waffler::cache::MemoryStore::default();
+    let json = Header::from_bytes("Content-Type", "application/json").unwrap();
+    for mut request in server.incoming_requests() {
+        let (status, body) = if *request.method() != Method::Post {
+            (405, r#"{"error":"Expected a POST request"}"#.to_owned())
+        } else {
+            let mut body = String::new();
+            match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => answer(&body, dict.as_ref(), &mut store),
+                Err(_) => (400, r#"{"error":"Expected the body to be UTF-8"}"#.to_owned()),
+            }
+        };
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(json.clone());
+        if let Err(err) = request.respond(response) { eprintln!("Failed to respond: {}", err); }
+    }
+}
+
+fn show_solution(letters: &WaffleBoard, found: Option<(WaffleBoard, Vec<Swap>)>) {
+    let (solved, steps) = match found {
+        Some(found) => found,
+        None => {
+            println!("Could not find a solution.");
+            return;
+        },
+    };
+
+    println!("{}", solved.display());
+    println!();
+    let mut cur = letters.normalized();
+    for step in steps {
+        println!("- {}", cur.describe_swap(step, CoordStyle::Zero));
+        cur = cur.swap(step);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("auto") => auto(&args[1..]),
+        Some("best") => best(&args[1..]),
+        Some("batch") => batch(&args[1..]),
+        Some("watch") => watch(&args[1..]),
+        #[cfg(feature = "http")]
+        Some("http") => http(&args[1..]),
+        #[cfg(feature = "edit")]
+        Some("edit") => edit(&args[1..]),
+        Some(command) => {
+            eprintln!("Unknown command: {}", command);
+            process::exit(1);
+        },
+        None => {
+            eprintln!("Expected a command: auto, best, batch, or watch");
+            process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,55 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Helpers for the integration tests, which run the binaries themselves. Each test file only uses
+// some of them.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+// The answer most of the tests work towards, and a word list holding its words: loyal, locus and
+// chair across, and lilac, yucca and loser down.
+pub const LOYAL: &str = "loyal\ni u o\nlocus\na c e\nchair\n";
+pub const WORDS: &str = "loyal\nlocus\nchair\nlilac\nyucca\nloser\n";
+
+// A directory of its own in the temporary directory, which is removed along with everything in it
+// once the test is done with it. Tests run at the same time, so each should use a different name.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("waffler-{}-{}", process::id(), name));
+        fs::create_dir_all(&path).expect("Failed to make a test directory");
+        return Self { path: path };
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+
+    // Writes contents to a file in the directory.
+    pub fn file(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.path.join(name);
+        fs::write(&path, contents).expect("Failed to write a test file");
+        return path;
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,177 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Runs the findanswers binary itself, checking what it prints and how it exits.
+
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::{TempDir, WORDS};
+
+fn findanswers(args: &[&str], words: &Path, board: &Path) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_findanswers"))
+        .args(args)
+        .arg(words)
+        .arg(board)
+        .output()
+        .expect("Failed to run findanswers");
+}
+
+fn text(bytes: &[u8]) -> String {
+    return String::from_utf8(bytes.to_vec()).expect("findanswers printed invalid UTF-8");
+}
+
+#[test]
+fn an_empty_word_list_is_an_error() {
+    let dir = TempDir::new("empty");
+    let words = dir.file("words", "\n\n");
+    let board = dir.file("board", "LOYAL\ni u o\nlocus\na c e\nchair\n");
+    let output = findanswers(&[], &words, &board);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(text(&output.stderr), "Error: word list is empty after filtering\n");
+}
+
+#[test]
+fn a_word_list_without_any_words_that_fit_is_warned_about() {
+    let dir = TempDir::new("short");
+    let words = dir.file("words", "cat\ndog\n");
+    let board = dir.file("board", "LOYAL\ni u o\nlocus\na c e\nchair\n");
+    let output = findanswers(&[], &words, &board);
+    assert!(output.status.success());
+    assert_eq!(text(&output.stderr), "Warning: word list has no words of length 5\n");
+    assert_eq!(text(&output.stdout), "");
+}
+
+#[test]
+fn searching_on_several_threads_finds_the_same_solutions_in_the_same_order() {
+    // Only the corners on the diagonal are known, so the answer and its transpose both fit.
+    let dir = TempDir::new("jobs");
+    let words = dir.file("words", WORDS);
+    let board = dir.file("board", "Loyal\ni u o\nlocus\na c e\nchaiR\n");
+    let serial = findanswers(&["--jobs", "1"], &words, &board);
+    assert!(serial.status.success());
+    assert_eq!(text(&serial.stdout),
+               "loyal\ni u o\nlocus\na c e\nchair\n\nlilac\no o h\nyucca\na u i\nloser\n");
+    for jobs in ["2", "3"] {
+        let parallel = findanswers(&["--jobs", jobs], &words, &board);
+        assert!(parallel.status.success());
+        assert_eq!(text(&parallel.stdout), text(&serial.stdout));
+        let first = findanswers(&["--jobs", jobs, "--first"], &words, &board);
+        assert_eq!(text(&first.stdout), "loyal\ni u o\nlocus\na c e\nchair\n");
+    }
+}
+
+#[test]
+fn first_prints_only_one_of_several_solutions() {
+    let dir = TempDir::new("first");
+    let words = dir.file("words", WORDS);
+    let board = dir.file("board", "Loyal\ni u o\nlocus\na c e\nchaiR\n");
+    let output = findanswers(&["--first"], &words, &board);
+    assert!(output.status.success());
+    assert_eq!(text(&output.stdout), "loyal\ni u o\nlocus\na c e\nchair\n");
+}
+
+#[test]
+fn max_branch_stops_at_a_word_with_too_many_candidates() {
+    let dir = TempDir::new("max-branch");
+    let words = dir.file("words", WORDS);
+    let board = dir.file("board", "Loyal\ni u o\nlocus\na c e\nchaiR\n");
+    let output = findanswers(&["--max-branch", "3"], &words, &board);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(text(&output.stderr),
+               "Error: a word has 4 candidates, more than --max-branch allows\n");
+    assert_eq!(text(&output.stdout), "");
+}
+
+#[test]
+fn truncating_keeps_the_most_used_candidates() {
+    // The rare words come first in the list, and would crowd out every real candidate if the list
+    // were cut short in its own order.
+    let dir = TempDir::new("truncate");
+    let words = dir.file("words", "lzzza 1\nlzzzb 1\nlzzzc 1\nlzzzd 1\nloyal 10\n\
+                                   locus 10\nchair 10\nlilac 10\nyucca 10\nloser 10\n");
+    let board = dir.file("board", "Loyal\ni u o\nlocus\na c e\nchaiR\n");
+    let output = findanswers(&["--max-branch", "4", "--max-branch-truncate"], &words, &board);
+    assert!(output.status.success());
+    assert_eq!(text(&output.stdout), "loyal\ni u o\nlocus\na c e\nchair\n");
+    assert_eq!(text(&output.stderr), "Warning: some words had too many candidates and were \
+                                      truncated; solutions may be missing\n");
+}
+
+#[test]
+fn letters_rules_out_words_needing_other_tiles() {
+    // locus and locum both fit the middle row, but the rack has an m where the board has an s.
+    let dir = TempDir::new("letters");
+    let words = dir.file("words", "loyal\nlocus\nlocum\nchair\nlilac\nyucca\nloser\nlomer\n");
+    let board = dir.file("board", "LOYAL\ni u o\nlocus\na c e\nchair\n");
+    let output = findanswers(&[], &words, &board);
+    assert_eq!(text(&output.stdout), "loyal\ni u o\nlocus\na c e\nchair\n");
+    let output = findanswers(&["--letters", "iuolocumacechair"], &words, &board);
+    assert!(output.status.success());
+    assert_eq!(text(&output.stdout), "loyal\ni u o\nlocum\na c e\nchair\n");
+}
+
+#[test]
+fn separator_goes_between_the_solutions_only() {
+    let dir = TempDir::new("separator");
+    let words = dir.file("words", WORDS);
+    let board = dir.file("board", "Loyal\ni u o\nlocus\na c e\nchaiR\n");
+    let output = findanswers(&["--separator", "==="], &words, &board);
+    assert!(output.status.success());
+    assert_eq!(text(&output.stdout),
+               "loyal\ni u o\nlocus\na c e\nchair\n===\nlilac\no o h\nyucca\na u i\nloser\n");
+}
+
+#[test]
+fn symbols_solves_a_grid_of_digits() {
+    // Digits have no case, so the known ones are marked with a 'g' in a grid of their own. Only two
+    // tiles are out of place. Without the marks nothing is known, and the transpose fits too.
+    let dir = TempDir::new("symbols");
+    let words = dir.file("words", "12345\n39012\n78901\n16347\n37059\n58261\n");
+    let board = dir.file("board", "10345\n6 7 8\n39012\n4 5 6\n78921\n");
+    let known = dir.file("known", "gxggg\ng g g\nggggg\ng g g\ngggxg\n");
+    let output = findanswers(&["--symbols", known.to_str().unwrap()], &words, &board);
+    assert!(output.status.success(), "{}", text(&output.stderr));
+    assert_eq!(text(&output.stdout), "12345\n6 7 8\n39012\n4 5 6\n78901\n");
+    let output = findanswers(&[], &words, &board);
+    assert_eq!(text(&output.stdout),
+               "12345\n6 7 8\n39012\n4 5 6\n78901\n\n16347\n2 9 8\n37059\n4 1 0\n58261\n");
+}
+
+#[test]
+fn locking_a_word_rules_out_the_solutions_without_it() {
+    // Only the corners on the diagonal are known, so the answer and its transpose both fit until
+    // the first row is locked to one of them.
+    let dir = TempDir::new("lock");
+    let words = dir.file("words", WORDS);
+    let board = dir.file("board", "Loyal\ni u o\nlocus\na c e\nchaiR\n");
+    let output = findanswers(&[], &words, &board);
+    assert_eq!(text(&output.stdout),
+               "loyal\ni u o\nlocus\na c e\nchair\n\nlilac\no o h\nyucca\na u i\nloser\n");
+    for (lock, answer) in [("=LILAC@row0", "lilac\no o h\nyucca\na u i\nloser\n"),
+                           ("=LILAC@col0", "loyal\ni u o\nlocus\na c e\nchair\n")] {
+        let board = dir.file("board", &format!("Loyal\ni u o\nlocus\na c e\nchaiR\n{}\n", lock));
+        let output = findanswers(&[], &words, &board);
+        assert!(output.status.success(), "{}", text(&output.stderr));
+        assert_eq!(text(&output.stdout), answer, "{}", lock);
+    }
+
+    // A word which disagrees with the known letters is an error rather than no solutions.
+    let board = dir.file("board", "Loyal\ni u o\nlocus\na c e\nchaiR\n=YUCCA@row0\n");
+    let output = findanswers(&[], &words, &board);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(text(&output.stderr), format!("{}: Cannot lock in =YUCCA@row0\n", board.display()));
+    assert_eq!(text(&output.stdout), "");
+}
@@ -0,0 +1,374 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Runs the findswaps binary itself, checking what it prints and how it exits. The boards are
+// passed in WAFFLE_FROM and WAFFLE_TO. For brevity, the boards here have their rows separated by
+// '/', which findswaps itself would take as a tile, so it's swapped for a newline before they're
+// passed in.
+
+mod common;
+
+use std::process::{Command, Output};
+
+use common::{TempDir, LOYAL};
+use waffler::board::{Swap, WaffleBoard};
+
+fn findswaps(args: &[&str], from: &str, into: &str) -> Output {
+    return findswaps_raw(args, &from.replace('/', "\n"), &into.replace('/', "\n"));
+}
+
+// The same as findswaps, but with the boards passed in exactly as they're given.
+fn findswaps_raw(args: &[&str], from: &str, into: &str) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_findswaps"))
+        .args(args)
+        .env("WAFFLE_FROM", from)
+        .env("WAFFLE_TO", into)
+        .output()
+        .expect("Failed to run findswaps");
+}
+
+fn stdout(output: &Output) -> String {
+    return String::from_utf8(output.stdout.clone()).expect("findswaps printed invalid UTF-8");
+}
+
+#[test]
+fn stats_counts_the_optimal_solutions() {
+    // A cycle of three letters in the first row, which any two of its three swaps can fix.
+    let output = findswaps(&["--stats"], "oylal/i u o/locus/a c e/chair", LOYAL);
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("reachable: yes\nminimum swaps: 2\noptimal solutions: 3\n"),
+            "{}", stdout(&output));
+}
+
+#[test]
+fn stats_counts_same_letter_swaps_at_every_step_when_asked() {
+    // Two of the misplaced tiles are c's, and swapping those with each other would do nothing.
+    let from = "coycl/l u o/laous/a c e/ihair";
+    let output = findswaps(&["--stats", "--include-trivial"], from, LOYAL);
+    assert!(output.status.success());
+    assert!(stdout(&output).ends_with("\nsame-letter swaps skipped by step: 1 1 1 0 0\n"),
+            "{}", stdout(&output));
+    let output = findswaps(&["--stats"], from, LOYAL);
+    assert!(!stdout(&output).contains("same-letter"), "{}", stdout(&output));
+
+    // Even a search which never passes over one says so, rather than printing nothing.
+    let output = findswaps(&["--stats", "--include-trivial"], LOYAL, LOYAL);
+    assert!(stdout(&output).ends_with("\nsame-letter swaps skipped by step: 0\n"),
+            "{}", stdout(&output));
+}
+
+#[test]
+fn final_only_shows_the_target_and_the_swap_count() {
+    let output = findswaps(&["--final-only"], "oylal/i u o/locus/a c e/chair", LOYAL);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "loyal\ni u o\nlocus\na c e\nchair\n2 swaps\n");
+}
+
+#[test]
+fn boards_with_different_letters_are_unsolvable() {
+    let output = findswaps(&[], "loyal/i u o/locus/a c e/chaiz", LOYAL);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "Unsolvable: letter sets differ\n");
+}
+
+#[test]
+fn exhausting_the_search_reports_the_board_unreachable() {
+    // The 's' and the first 'l' have traded rows, which row swaps can never undo.
+    let output = findswaps(&["--swap-mode", "row"], "soyal/i u o/locul/a c e/chair", LOYAL);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "No path found (same letters but unreachable)\n");
+}
+
+#[test]
+fn running_out_of_swaps_reports_the_limit() {
+    // Every row is rotated by one, which takes 16 swaps within the rows to undo.
+    let output = findswaps(&["--swap-mode", "row"], "bcdea/g h f/jklmi/o p n/rstuq",
+                           "abcde/f g h/ijklm/n o p/qrstu");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output),
+               "No path found within 10 swaps (same letters, but longer paths were not searched)\n");
+}
+
+#[test]
+fn profile_reports_each_section_of_the_search() {
+    let testdata = concat!(env!("CARGO_MANIFEST_DIR"), "/src/testdata");
+    let output = Command::new(env!("CARGO_BIN_EXE_findswaps"))
+        .args(["--profile", &format!("{}/from", testdata), &format!("{}/into", testdata)])
+        .output()
+        .expect("Failed to run findswaps");
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let sections: Vec<&str> = stderr.lines()
+        .map(|line| line.split_whitespace().next().unwrap())
+        .collect();
+    assert_eq!(sections, ["section", "frontier", "map", "score", "swap"]);
+}
+
+#[test]
+fn swaps_format_reads_back_into_the_swaps_that_solve_the_board() {
+    let from = "lauol/s y l/cucoh/e i i/coaar";
+    let output = findswaps(&["--format", "swaps"], from, LOYAL);
+    assert!(output.status.success());
+    let steps: Vec<Swap> = stdout(&output).lines()
+        .map(|line| line.parse().unwrap_or_else(|err| panic!("{}: {}", line, err)))
+        .collect();
+    assert_eq!(steps.len(), 10);
+    let board = |rows: &str| rows.replace('/', "\n").parse::<WaffleBoard>().unwrap();
+    assert_eq!(board(from).apply_all(&steps), board(LOYAL));
+}
+
+#[test]
+fn reversed_swaps_lead_from_the_answer_back_to_the_scramble() {
+    let from = "coycl/l u o/laous/a c e/ihair";
+    let board = |rows: &str| rows.replace('/', "\n").parse::<WaffleBoard>().unwrap();
+    let output = findswaps(&["--reverse", "--format", "swaps"], from, LOYAL);
+    assert!(output.status.success());
+    let steps: Vec<Swap> = stdout(&output).lines().map(|line| line.parse().unwrap()).collect();
+    assert_eq!(steps.len(), 4);
+    assert_eq!(board(LOYAL).apply_all(&steps), board(from));
+
+    // The same swaps the other way round solve the puzzle.
+    let output = findswaps(&["--format", "swaps"], from, LOYAL);
+    let forward: Vec<Swap> = stdout(&output).lines().map(|line| line.parse().unwrap()).collect();
+    assert_eq!(forward.into_iter().rev().collect::<Vec<Swap>>(), steps);
+
+    let output = findswaps(&["--reverse", "--final-only"], from, LOYAL);
+    assert_eq!(stdout(&output), "coycl\nl u o\nlaous\na c e\nihair\n4 swaps\n");
+}
+
+#[test]
+fn boards_come_from_the_environment_only_without_files() {
+    // Rows may be separated by an escaped newline as well as by a real one.
+    let from = "lyoal\\ni u o\\nlocus\\na c e\\nchair";
+    let output = findswaps_raw(&["--format", "swaps"], from, LOYAL);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "0,1-0,2\n");
+
+    // A '/' is a tile like any other, not the end of a row.
+    let output = findswaps_raw(&["--format", "swaps"], "/yoal\\ni u o\\nlocus\\na c e\\nchair",
+                               "/oyal\ni u o\nlocus\na c e\nchair\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(stdout(&output), "0,1-0,2\n");
+
+    // Files given as arguments win over the environment.
+    let testdata = concat!(env!("CARGO_MANIFEST_DIR"), "/src/testdata");
+    let from = format!("{}/from", testdata);
+    let into = format!("{}/into", testdata);
+    let output = findswaps(&["--format", "swaps", &from, &into], "not a board", "nor this");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output).lines().count(), 10);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_findswaps"))
+        .env_remove("WAFFLE_FROM")
+        .env_remove("WAFFLE_TO")
+        .output()
+        .expect("Failed to run findswaps");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(),
+               "Expected board files as arguments, or boards in WAFFLE_FROM and WAFFLE_TO\n");
+}
+
+#[test]
+fn separator_goes_between_the_boards_only() {
+    let output = findswaps(&["--separator", "==="], "oylal/i u o/locus/a c e/chair", LOYAL);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "\
+oylal\ni u o\nlocus\na c e\nchair
+- swap 'o' at (0,0) with 'y' at (0,1)
+===
+yolal\ni u o\nlocus\na c e\nchair
+- swap 'y' at (0,0) with 'l' at (0,2)
+===
+loyal\ni u o\nlocus\na c e\nchair
+");
+}
+
+#[test]
+fn solutions_counts_or_shows_every_optimal_solution() {
+    // The two o's can each go to either of the two cells waiting for one, and the l's likewise.
+    let from = "olyao/i u l/locus/a c e/chair";
+    let output = findswaps(&["--solutions", "count"], from, LOYAL);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "2\n");
+
+    let output = findswaps(&["--solutions", "all"], from, LOYAL);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "\
+olyao\ni u l\nlocus\na c e\nchair
+- swap 'o' at (0,0) with 'l' at (0,1)
+loyao\ni u l\nlocus\na c e\nchair
+- swap 'o' at (0,4) with 'l' at (1,4)
+loyal\ni u o\nlocus\na c e\nchair
+
+olyao\ni u l\nlocus\na c e\nchair
+- swap 'o' at (0,0) with 'l' at (1,4)
+llyao\ni u o\nlocus\na c e\nchair
+- swap 'l' at (0,1) with 'o' at (0,4)
+loyal\ni u o\nlocus\na c e\nchair
+");
+}
+
+#[test]
+fn target_words_stand_in_for_the_target_board() {
+    let words = "row0=loyal row2=locus row4=chair col0=lilac col2=yucca col4=loser";
+    // The target in the environment is wrong, and is ignored.
+    let output = findswaps(&["--target-words", words, "--final-only"],
+                           "oylal/i u o/locus/a c e/chair", "oylal/i u o/locus/a c e/chair");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "loyal\ni u o\nlocus\na c e\nchair\n2 swaps\n");
+
+    let conflict = words.replace("col2=yucca", "col2=bucca");
+    let output = findswaps(&["--target-words", &conflict], "oylal/i u o/locus/a c e/chair", LOYAL);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(),
+               "--target-words: Words disagree at (0,2): 'y' vs 'b'\n");
+}
+
+#[test]
+fn cross_check_agrees_across_the_corpus() {
+    // Scrambles of every kind, from a single swap up to a long cycle, along with the solved board
+    // and one which can't be solved at all. The restricted modes make the longest one take nine
+    // swaps, which is too slow to check here.
+    let all = ["any", "line", "row"].as_slice();
+    let corpus = [
+        ("loyal/i u o/locus/a c e/chair", all),
+        ("loyal/i u o/locus/a c e/chiar", all),
+        ("oylal/i u o/locus/a c e/chair", all),
+        ("olyao/i u l/locus/a c e/chair", all),
+        ("coycl/l u o/laous/a c e/ihair", all),
+        ("loyel/i o o/ilcuu/a c s/chaar", ["any"].as_slice()),
+        ("loyal/i u o/locus/a c e/chaiz", all),
+    ];
+    for (from, modes) in corpus {
+        for &mode in modes {
+            let output = findswaps(&["--cross-check", "--swap-mode", mode], from, LOYAL);
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            assert!(output.status.success(), "{} ({}): {}", from, mode, stderr);
+            assert_eq!(stderr, "Cross-check passed\n", "{} ({})", from, mode);
+        }
+    }
+}
+
+#[test]
+fn word_progress_lists_more_complete_words_until_all_are() {
+    let output = findswaps(&["--word-progress"], "coycl/l u o/laous/a c e/ihair", LOYAL);
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    // Each frame is a board, followed by the words it completed, then the next swap.
+    let mut complete = Vec::new();
+    let mut progress = Vec::new();
+    for frame in stdout.split("\n- ") {
+        let words = frame.lines()
+            .filter_map(|line| line.strip_prefix("complete: "))
+            .flat_map(|words| words.split(", "));
+        complete.extend(words);
+        progress.push(complete.len());
+    }
+    assert_eq!(progress, [1, 1, 1, 3, 6], "{}", stdout);
+    complete.sort();
+    assert_eq!(complete, ["col 0", "col 2", "col 4", "row 0", "row 2", "row 4"]);
+    assert!(stdout.ends_with("chair\ncomplete: row 0, row 4, col 0\n"), "{}", stdout);
+}
+
+#[test]
+fn explain_names_the_words_each_swap_completes() {
+    let from = "coycl/l u o/laous/a c e/ihair";
+    let output = findswaps(&["--explain"], from, LOYAL);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("\
+- swap 'c' at (0,3) with 'a' at (2,2)
+  completes LOCUS (row 2)
+  completes YUCCA (col 2)
+coyal
+"), "{}", stdout(&output));
+    assert!(stdout(&output).contains("\
+- swap 'c' at (0,0) with 'l' at (4,0)
+  completes LOYAL (row 0)
+  completes CHAIR (row 4)
+  completes LILAC (col 0)
+loyal
+"), "{}", stdout(&output));
+
+    // Given a word list, a swap which spells some other word from it is pointed out as well.
+    let dir = TempDir::new("explain-words");
+    let words = dir.file("words", "coyal\nloyal\n");
+    let output = findswaps(&["--explain-words", words.to_str().unwrap()], from, LOYAL);
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    let explanations: Vec<&str> = stdout.lines()
+        .filter(|line| line.starts_with("  "))
+        .map(str::trim)
+        .collect();
+    assert_eq!(explanations, [
+        "spells COYAL (row 0), which is a word but not the answer",
+        "completes LOCUS (row 2)",
+        "completes YUCCA (col 2)",
+        "completes LOYAL (row 0)",
+        "completes CHAIR (row 4)",
+        "completes LILAC (col 0)",
+    ]);
+}
+
+#[test]
+fn dry_run_lists_the_swaps_without_any_boards() {
+    let output = findswaps(&["--dry-run"], "oylal/i u o/locus/a c e/chiar", LOYAL);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "plan: 3 swaps (not applied)\n4,2-4,3\n0,0-0,1\n0,0-0,2\n");
+}
+
+// Splits a line of CSV into its fields, undoing the quoting of any which needed it.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    return fields;
+}
+
+#[test]
+fn csv_reads_back_into_the_solution_even_with_tiles_that_need_quoting() {
+    // A comma and a quote for tiles, which have to be quoted to keep each row to seven fields.
+    let (from, into) = ("o,yal/i u o/locus/a c e/chai\"", ",oyal/i u o/locus/a c e/cha\"i");
+    let output = findswaps(&["--format", "csv"], from, into);
+    assert!(output.status.success());
+    let text = stdout(&output);
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("step,letter_a,row_a,col_a,letter_b,row_b,col_b"));
+
+    let board = |rows: &str| rows.replace('/', "\n").parse::<WaffleBoard>().unwrap();
+    let mut cur = board(from);
+    for (i, line) in lines.enumerate() {
+        let fields = csv_fields(line);
+        assert_eq!(fields.len(), 7, "{}", line);
+        assert_eq!(fields[0], (i + 1).to_string());
+        let step: Swap = format!("{},{}-{},{}", fields[2], fields[3], fields[5], fields[6])
+            .parse()
+            .unwrap();
+        assert_eq!(fields[1], cur.get(step.a).to_string());
+        assert_eq!(fields[4], cur.get(step.b).to_string());
+        cur = cur.swap(step);
+    }
+    assert_eq!(cur, board(into));
+    assert!(text.contains("\",\""), "{}", text);
+    assert!(text.contains("\"\"\"\""), "{}", text);
+}
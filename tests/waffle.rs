@@ -0,0 +1,230 @@
+// This file is part of WaffleSolver.
+//
+// WaffleSolver is free software: you can redistribute it and/or modify it under the terms of the
+// GNU General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// WaffleSolver is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with WaffleSolver. If
+// not, see <https://www.gnu.org/licenses/>.
+
+// Runs the waffle binary itself, checking what its subcommands print.
+
+mod common;
+
+use std::process::{Command, Output};
+
+use common::TempDir;
+
+fn waffle(args: &[&str]) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_waffle"))
+        .args(args)
+        .output()
+        .expect("Failed to run waffle");
+}
+
+fn text(bytes: &[u8]) -> String {
+    return String::from_utf8(bytes.to_vec()).expect("waffle printed invalid UTF-8");
+}
+
+#[test]
+fn auto_solves_a_whole_puzzle_from_its_letters_and_colours() {
+    // The answer is loyal, locus and chair across, with seven of its tiles sent round a cycle, so
+    // it takes six swaps. The colours are the game's: green in place, yellow elsewhere in one of
+    // the tile's words, and grey (written x) otherwise. The list has words which don't fit too.
+    let dir = TempDir::new("auto");
+    let words = dir.file("words", "loyal\nlocus\nchair\nlilac\nyucca\nloser\nroyal\nlocal\n\
+                                   lilas\nsalon\nhello\nloyer\ncoyal\nlaced\nclear\n");
+    let letters = dir.file("letters", "loyel\ni o o\nilcuu\na c s\nchaar\n");
+    let colors = dir.file("colors", "gggxg\ng x g\nxyggx\ng g y\ngggxg\n");
+    let output = waffle(&["auto", words.to_str().unwrap(), letters.to_str().unwrap(),
+                          colors.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", text(&output.stderr));
+    assert_eq!(text(&output.stdout), "\
+loyal
+i u o
+locus
+a c e
+chair
+
+- swap 'e' at (0,3) with 's' at (3,4)
+- swap 's' at (0,3) with 'u' at (2,4)
+- swap 'u' at (0,3) with 'o' at (1,2)
+- swap 'o' at (0,3) with 'l' at (2,1)
+- swap 'l' at (0,3) with 'i' at (2,0)
+- swap 'i' at (0,3) with 'a' at (4,3)
+");
+}
+
+#[test]
+fn auto_help_says_only_greens_are_used() {
+    let output = waffle(&["auto", "--help"]);
+    assert!(output.status.success());
+    assert!(text(&output.stdout).contains("Only the greens are used"), "{}", text(&output.stdout));
+}
+
+#[test]
+fn best_prefers_the_most_common_words_unless_told_to_prefer_swaps() {
+    // Both boards can be made from the letters: layol and lucus across, one swap away, or the far
+    // more common luyal and locus, three swaps away.
+    let dir = TempDir::new("prefer");
+    let words = dir.file("words", "layol 1\nlucus 1\nluyal 50\nlocus 50\nchair\nlilac\n\
+                                   yucca\nloser\n");
+    let letters = dir.file("letters", "layol\ni u o\nlucus\na c e\ncahir\n");
+    let best = |prefer: &str| {
+        let output = waffle(&["best", "--prefer", prefer, words.to_str().unwrap(),
+                              letters.to_str().unwrap()]);
+        assert!(output.status.success(), "{}", text(&output.stderr));
+        return text(&output.stdout);
+    };
+    assert_eq!(best("likelihood"), "\
+luyal
+i u o
+locus
+a c e
+chair
+
+- swap 'a' at (0,1) with 'o' at (0,3)
+- swap 'o' at (0,1) with 'u' at (2,1)
+- swap 'a' at (4,1) with 'h' at (4,2)
+");
+    assert_eq!(best("swaps"), "\
+layol
+i u o
+lucus
+a c e
+chair
+
+- swap 'a' at (4,1) with 'h' at (4,2)
+");
+}
+
+#[test]
+fn batch_solves_each_puzzle_in_a_directory_and_marks_the_unsolvable_ones() {
+    // The line between the boards may hold stray whitespace. The last puzzle's letters can't make
+    // its answer, so it has no solution at all.
+    let dir = TempDir::new("batch");
+    dir.file("a.txt", "loyal\ni u o\nlocus\na c e\nchair\n\nloyal\ni u o\nlocus\na c e\nchair\n");
+    dir.file("b.txt", "lyoal\ni u o\nlocus\na c e\nchair\n  \nloyal\ni u o\nlocus\na c e\nchair\n");
+    dir.file("c.txt", "loyal\ni u o\nlocus\na c e\nchaiz\n\t\nloyal\ni u o\nlocus\na c e\nchair\n");
+    dir.file("notes", "not a puzzle");
+    let output = waffle(&["batch", dir.path().to_str().unwrap()]);
+    assert!(output.status.success(), "{}", text(&output.stderr));
+    assert_eq!(text(&output.stdout), "\
+file   swaps  solvable
+a.txt      0  yes
+b.txt      1  yes
+c.txt         no
+");
+    assert_eq!(text(&output.stderr), "");
+}
+
+#[test]
+fn headless_watch_prints_every_frame_in_turn() {
+    let dir = TempDir::new("watch");
+    let from = dir.file("from", "oylal\ni u o\nlocus\na c e\nchair\n");
+    let into = dir.file("into", "loyal\ni u o\nlocus\na c e\nchair\n");
+    let output = waffle(&["watch", "--headless", from.to_str().unwrap(), into.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", text(&output.stderr));
+    let stdout = text(&output.stdout);
+    let frames: Vec<&str> = stdout.trim_end().split("\n\n").collect();
+    assert_eq!(frames.len(), 3, "{}", stdout);
+    assert_eq!(frames[0], "step 0 of 2: start\noylal\ni u o\nlocus\na c e\nchair");
+    assert!(frames[1].starts_with("step 1 of 2: "), "{}", frames[1]);
+    assert!(frames[2].starts_with("step 2 of 2: "), "{}", frames[2]);
+    assert!(frames[2].ends_with("\nloyal\ni u o\nlocus\na c e\nchair"), "{}", frames[2]);
+}
+
+// Sends a request to waffle http, returning the status code and the body of the reply.
+#[cfg(feature = "http")]
+fn request(port: u16, method: &str, body: &str) -> (u16, String) {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("Failed to connect");
+    write!(stream, "{} / HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\
+                    Connection: close\r\n\r\n{}", method, body.len(), body).unwrap();
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+    let (head, body) = reply.split_once("\r\n\r\n").expect("No end to the headers");
+    let status = head.split(' ').nth(1).and_then(|status| status.parse().ok());
+    return (status.expect("No status in the reply"), body.to_owned());
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn http_answers_a_posted_puzzle_with_its_swaps() {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::process::{Child, Stdio};
+
+    use waffler::board::{Swap, WaffleBoard};
+
+    // Stops the server however the test ends.
+    struct Server(Child);
+
+    impl Drop for Server {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    // A port nothing else is using, as far as can be told.
+    let port = TcpListener::bind(("127.0.0.1", 0)).unwrap().local_addr().unwrap().port();
+    let mut server = Server(Command::new(env!("CARGO_BIN_EXE_waffle"))
+        .args(["http", "--port", &port.to_string()])
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run waffle"));
+    let mut listening = String::new();
+    BufReader::new(server.0.stderr.take().unwrap()).read_line(&mut listening).unwrap();
+    assert_eq!(listening, format!("Listening on http://127.0.0.1:{}\n", port));
+
+    let from = "coycl\nl u o\nlaous\na c e\nihair\n";
+    let into = "loyal\ni u o\nlocus\na c e\nchair\n";
+    let puzzle = serde_json::json!({ "from": from, "into": into }).to_string();
+    let replies = [
+        request(port, "POST", &puzzle),
+        request(port, "POST", &puzzle.replace("ihair", "ihaiz")),
+        request(port, "POST", "{}"),
+        request(port, "GET", ""),
+    ];
+
+    let (status, body) = &replies[0];
+    assert_eq!(*status, 200, "{}", body);
+    let reply: serde_json::Value = serde_json::from_str(body).unwrap();
+    let steps: Vec<Swap> = serde_json::from_value(reply["swaps"].clone()).unwrap();
+    assert_eq!(steps.len(), 4);
+    let board = |text: &str| text.parse::<WaffleBoard>().unwrap();
+    assert_eq!(board(from).apply_all(&steps), board(into));
+
+    assert_eq!(replies[1], (422, r#"{"error":"Unsolvable: letter sets differ"}"#.to_owned()));
+    assert_eq!(replies[2].0, 400);
+    assert_eq!(replies[3], (405, r#"{"error":"Expected a POST request"}"#.to_owned()));
+}
+
+#[test]
+fn batch_flags_puzzles_needing_more_swaps_than_allowed() {
+    let dir = TempDir::new("flag-over");
+    let answer = "loyal\ni u o\nlocus\na c e\nchair\n";
+    dir.file("easy.txt", &format!("lyoal\ni u o\nlocus\na c e\nchair\n\n{}", answer));
+    dir.file("hard.txt", &format!("coycl\nl u o\nlaous\na c e\nihair\n\n{}", answer));
+    dir.file("none.txt", &format!("loyal\ni u o\nlocus\na c e\nchaiz\n\n{}", answer));
+    let output = waffle(&["batch", "--flag-over", "2", "--format", "csv",
+                          dir.path().to_str().unwrap()]);
+    assert!(output.status.success(), "{}", text(&output.stderr));
+    assert_eq!(text(&output.stdout), "\
+file,swaps,solvable,flagged
+easy.txt,1,true,false
+hard.txt,4,true,true
+none.txt,,false,true
+");
+    // Right at the limit isn't over it.
+    let output = waffle(&["batch", "--flag-over", "4", "--format", "csv",
+                          dir.path().to_str().unwrap()]);
+    assert!(text(&output.stdout).contains("\nhard.txt,4,true,false\n"), "{}", text(&output.stdout));
+}